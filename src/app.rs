@@ -1,77 +1,684 @@
 use anyhow::Result;
+use std::path::Path;
+use chrono::{Datelike, Local, Months, Utc};
 
-use crate::cli::{Cli, Commands, ProjectCommands};
-use crate::io::TaskIO;
-use crate::storage::TaskStorage;
-use crate::utils::parse_to_unix;
+use crate::config::{Config, GitProjects, TaskSort, write_config_value};
+use crate::cli::{AliasCommands, Cli, Commands, ConfigCommands, ProfileCommands, ProjectCommands, TemplateCommands, WorkspaceCommands};
+use crate::io::{ListOptions, TaskIO};
+use crate::storage::{DEFAULT_PROFILE, Source, TaskStorage, delete_profile, get_profile_path, get_profiles, profile_exists};
+use crate::types::{AgendaEntry, Project, SearchResult};
+use crate::utils::{find_git_root, suggest, local_midnight, parse_month, parse_to_unix, start_of_day};
+
+fn get_project_id_from_input_or_current(
+    storage: &TaskStorage,
+    config: &Config,
+    io: &TaskIO,
+    input: Option<String>,
+    global: bool,
+) -> Result<Option<u32>> {
+    if global {
+        return storage.get_global_project();
+    }
 
-fn get_project_id_from_input_or_current(storage: &TaskStorage, input: Option<String>) -> Result<Option<u32>> {
     Ok(match input {
-        Some(input) => storage.find_project_by_dir_name(&input)?,
-        None => storage.get_current_project()?,
+        Some(input) => find_project_by_dir_name(storage, io, &input)?,
+        None => match storage.get_current_project()? {
+            // an unregistered git repository that will be registered on the first
+            // write is more specific than the global project
+            None if config.git_projects == GitProjects::Create && get_git_root(config)?.is_some() => None,
+            None if config.global_fallback => storage.get_global_project()?,
+            project_id => project_id,
+        },
     })
 }
 
-pub fn run(storage: &TaskStorage, io: &mut TaskIO, cli: Cli) -> Result<()> {
+/// Resolves a project given by name, path or directory name. When several
+/// projects match, the user chooses on a terminal. Otherwise, and when no
+/// project matches, the reason is printed and `None` returned.
+fn find_project_by_dir_name(storage: &TaskStorage, io: &TaskIO, name: &str) -> Result<Option<u32>> {
+    let candidates = storage.find_projects_by_dir_name(name)?;
+
+    match candidates.as_slice() {
+        [] => {
+            let names = storage.get_project_names()?;
+            let suggestions = suggest(name, &names);
+            if !suggestions.is_empty() {
+                io.project_not_found_suggest(name, &suggestions);
+            }
+            Ok(None)
+        },
+        [project] => Ok(Some(project.id)),
+        _ if io.is_interactive() => io.choose_project(name, &candidates),
+        _ => {
+            io.project_ambiguous(name, &candidates);
+            Ok(None)
+        },
+    }
+}
+
+/// Same as `get_project_id_from_input_or_current`, but registers the enclosing
+/// git repository as a project when configured to, for commands adding tasks.
+fn get_or_create_project_id(
+    storage: &TaskStorage,
+    config: &Config,
+    io: &TaskIO,
+    input: Option<String>,
+    global: bool,
+) -> Result<Option<u32>> {
+    let create = input.is_none() && !global && config.git_projects == GitProjects::Create;
+    let project_id = get_project_id_from_input_or_current(storage, config, io, input, global)?;
+
+    match get_git_root(config)? {
+        Some(root) if project_id.is_none() && create => {
+            let project_id = storage.new_project_at(Path::new(&root))?;
+            io.new_git_project(&root);
+            Ok(Some(project_id))
+        },
+        _ => Ok(project_id),
+    }
+}
+
+/// The unregistered git repository the current directory belongs to, as an empty project.
+fn get_git_project(config: &Config) -> Result<Option<Project>> {
+    Ok(get_git_root(config)?.map(|path| Project { path, ..Default::default() }))
+}
+
+fn get_git_root(config: &Config) -> Result<Option<String>> {
+    if config.git_projects == GitProjects::Off {
+        return Ok(None);
+    }
+
+    Ok(find_git_root(&std::env::current_dir()?).map(|root| root.to_string_lossy().to_string()))
+}
+
+/// The configuration with the `.taskrc.toml` of the project layered on top.
+fn project_config(storage: &TaskStorage, config: &Config, project_id: Option<u32>) -> Result<Config> {
+    match project_id.map(|id| storage.get_project(id)).transpose()?.flatten() {
+        Some(project) => config.for_project(&project.path),
+        None => Ok(config.clone()),
+    }
+}
+
+/// The configuration that applies in the current directory.
+fn current_config(storage: &TaskStorage, config: &Config) -> Result<Config> {
+    match storage.get_current_project()? {
+        Some(project_id) => project_config(storage, config, Some(project_id)),
+        None => match get_git_root(config)? {
+            Some(root) => config.for_project(&root),
+            None => Ok(config.clone()),
+        },
+    }
+}
+
+/// `--hide-finished` and `--show-finished` take precedence over the project
+/// setting, which takes precedence over the configuration.
+fn list_options(config: &Config, project: &Project, hide_override: Option<bool>) -> Result<ListOptions> {
+    let config = config.for_project(&project.path)?;
+    Ok(ListOptions {
+        hide_finished: hide_override.or(project.settings.hide_finished).unwrap_or(config.hide_finished),
+        sort: config.sort,
+    })
+}
+
+/// Whether `name` already resolves to a project other than `project_id`.
+fn is_name_taken(storage: &TaskStorage, project_id: u32, name: &str) -> Result<bool> {
+    Ok(storage.find_projects_by_dir_name(name)?.iter().any(|p| p.id != project_id))
+}
+
+/// Keeps only the agenda entries of the projects in `workspace`, if given.
+/// Returns false if the workspace does not exist.
+fn retain_workspace(storage: &TaskStorage, io: &TaskIO, workspace: Option<&str>, entries: &mut Vec<AgendaEntry>) -> Result<bool> {
+    let Some(name) = workspace else {
+        return Ok(true);
+    };
+    match storage.get_workspace(name)? {
+        Some(workspace) => {
+            entries.retain(|e| e.source.is_none() && workspace.projects.iter().any(|p| p.id == e.task.project_id));
+            Ok(true)
+        },
+        None => {
+            io.workspace_not_found();
+            Ok(false)
+        },
+    }
+}
+
+/// Unfinished tasks due in `[from, to)` from the main database and every
+/// additional one, soonest first.
+fn get_tasks_due_between(storage: &TaskStorage, sources: &[Source], io: &TaskIO, from: i64, to: i64) -> Result<Vec<AgendaEntry>> {
+    let mut entries = storage.get_tasks_due_between(from, to)?;
+    for (source, source_entries) in read_sources(sources, io, |storage| storage.get_tasks_due_between(from, to)) {
+        entries.extend(source_entries.into_iter().map(|e| AgendaEntry { source: Some(source.name.clone()), ..e }));
+    }
+    // the sort is stable, so tasks due at the same time keep their order
+    entries.sort_by_key(|e| e.task.expiration);
+
+    Ok(entries)
+}
+
+fn search_tasks(storage: &TaskStorage, sources: &[Source], io: &TaskIO, query: &str) -> Result<Vec<SearchResult>> {
+    let mut results = storage.search_tasks(query)?;
+    for (source, source_results) in read_sources(sources, io, |storage| storage.search_tasks(query)) {
+        results.extend(source_results.into_iter().map(|r| SearchResult { source: Some(source.name.clone()), ..r }));
+    }
+
+    Ok(results)
+}
+
+/// Reads every additional database with `read`. A database that cannot be
+/// read, such as a corrupt or locked one, is skipped with a warning instead of
+/// failing the whole command.
+fn read_sources<'a, T>(sources: &'a [Source], io: &TaskIO, read: impl Fn(&TaskStorage) -> Result<T>) -> Vec<(&'a Source, T)> {
+    let mut results = Vec::new();
+    for source in sources {
+        match read(&source.storage) {
+            Ok(result) => results.push((source, result)),
+            Err(error) => io.database_unreadable(&source.name, &error),
+        }
+    }
+
+    results
+}
+
+/// The unarchived projects of the main database and the additional ones.
+fn get_all_projects(storage: &TaskStorage, sources: &[Source], io: &TaskIO) -> Result<Vec<Project>> {
+    let mut projects = storage.get_all_projects()?;
+    for (source, source_projects) in read_sources(sources, io, |storage| storage.get_all_projects()) {
+        projects.extend(source_projects.into_iter().map(|p| Project { source: Some(source.name.clone()), ..p }));
+    }
+    projects.retain(|p| !p.archived);
+
+    Ok(projects)
+}
+
+/// Every task of the unarchived projects in the main database and the
+/// additional ones, with its number within its project.
+fn get_all_task_entries(storage: &TaskStorage, sources: &[Source], io: &TaskIO) -> Result<Vec<AgendaEntry>> {
+    let mut entries = Vec::new();
+    for project in get_all_projects(storage, sources, io)? {
+        for (index, task) in project.tasks.into_iter().enumerate() {
+            entries.push(AgendaEntry {
+                project_path: project.path.clone(),
+                project_name: project.name.clone(),
+                project_color: project.settings.color.clone(),
+                number: index + 1,
+                task,
+                source: project.source.clone(),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Projects of the additional databases with the same directory as `project`.
+fn get_source_projects(
+    sources: &[Source],
+    config: &Config,
+    io: &TaskIO,
+    project: &Project,
+    hide_override: Option<bool>,
+) -> Result<Vec<(Project, ListOptions)>> {
+    let mut projects = Vec::new();
+    for (source, source_project) in read_sources(sources, io, |storage| storage.get_project_by_path(&project.path)) {
+        if let Some(source_project) = source_project.filter(|p| !p.archived) {
+            let options = list_options(config, &source_project, hide_override)?;
+            projects.push((Project { source: Some(source.name.clone()), ..source_project }, options));
+        }
+    }
+
+    Ok(projects)
+}
+
+fn finished_override(hide_finished: bool, show_finished: bool) -> Option<bool> {
+    match (hide_finished, show_finished) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// Template offsets are relative to the anchor, which defaults to now.
+fn parse_anchor(config: &Config, input: Option<&str>) -> Option<i64> {
+    match input {
+        Some(input) => parse_to_unix(input, config.date_order),
+        None => Some(Utc::now().timestamp()),
+    }
+}
+
+pub fn run(storage: &TaskStorage, sources: &[Source], config: &Config, io: &mut TaskIO, cli: Cli) -> Result<()> {
     match cli.command {
         Commands::Project { command } => {
             match command {
-                ProjectCommands::New => {
-                    storage.new_project()?;
-                    io.new_project();
+                ProjectCommands::New { marker } => {
+                    if storage.new_project(marker)? {
+                        io.new_project();
+                    } else if marker && storage.mark_project(&std::env::current_dir()?)? {
+                        io.project_marked();
+                    } else {
+                        io.project_already_exists();
+                    }
+                },
+                ProjectCommands::List { archived } => {
+                    let projects = storage
+                        .get_all_projects()?
+                        .into_iter()
+                        .filter(|p| archived || !p.archived)
+                        .collect::<Vec<_>>();
+                    io.list_projects(&projects)?;
+                },
+                ProjectCommands::Archive { project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        Some(project_id) if storage.get_global_project()? == Some(project_id) => {
+                            io.cannot_archive_global_project();
+                        },
+                        Some(project_id) => storage.set_project_archived(project_id, true)?,
+                        None => io.project_not_found(),
+                    }
                 },
-                ProjectCommands::List => {
-                    io.list_projects(&storage.get_all_projects()?);
+                ProjectCommands::Unarchive { project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        Some(project_id) => storage.set_project_archived(project_id, false)?,
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Move { project, path } => {
+                    match find_project_by_dir_name(storage, io, &project)? {
+                        Some(project_id) if storage.get_global_project()? == Some(project_id) => {
+                            io.cannot_relink_global_project();
+                        },
+                        Some(_) if !Path::new(&path).is_dir() => io.not_a_directory(),
+                        Some(project_id) => {
+                            if !storage.move_project(project_id, Path::new(&path))? {
+                                io.directory_taken();
+                            }
+                        },
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Set { key, value, project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        Some(project_id) => {
+                            // validates the key and value before storing them
+                            storage.get_project_settings(project_id)?.set(&key, Some(&value))?;
+                            storage.set_project_setting(project_id, &key, &value)?;
+                        },
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Unset { key, project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        Some(project_id) => {
+                            storage.get_project_settings(project_id)?.set(&key, None)?;
+                            storage.unset_project_setting(project_id, &key)?;
+                        },
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Info { project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        // unwrap is safe because project_id exists
+                        Some(project_id) => io.print_project_info(&storage.get_project(project_id)?.unwrap())?,
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Rename { name, project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match project_id {
+                        Some(project_id) if is_name_taken(storage, project_id, &name)? => io.project_name_taken(),
+                        Some(project_id) => storage.rename_project(project_id, &name)?,
+                        None => io.project_not_found(),
+                    }
+                },
+                ProjectCommands::Alias { command } => {
+                    match command {
+                        AliasCommands::Add { alias, project } => {
+                            let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                            match project_id {
+                                Some(project_id) if is_name_taken(storage, project_id, &alias)? => io.project_name_taken(),
+                                Some(project_id) => {
+                                    if !storage.add_project_alias(project_id, &alias)? {
+                                        io.alias_already_exists();
+                                    }
+                                },
+                                None => io.project_not_found(),
+                            }
+                        },
+                        AliasCommands::Remove { alias } => {
+                            if !storage.remove_project_alias(&alias)? {
+                                io.alias_not_found();
+                            }
+                        },
+                    }
+                },
+                ProjectCommands::Prune { reassign, no_confirm } => {
+                    let missing = storage
+                        .get_all_projects()?
+                        .into_iter()
+                        .filter(|p| p.is_missing())
+                        .collect::<Vec<_>>();
+                    let reassign_id = match reassign {
+                        Some(reassign) => match find_project_by_dir_name(storage, io, &reassign)? {
+                            Some(project_id) => Some(project_id),
+                            None => {
+                                io.project_not_found();
+                                return Ok(());
+                            },
+                        },
+                        None => None,
+                    };
+
+                    if missing.is_empty() {
+                        io.no_missing_projects();
+                    } else if no_confirm || !config.confirm || io.confirm_prune_projects(&missing, reassign_id.is_some())? {
+                        for project in missing.iter().filter(|p| Some(p.id) != reassign_id) {
+                            if let Some(reassign_id) = reassign_id {
+                                storage.reassign_tasks(project.id, reassign_id)?;
+                            }
+                            storage.delete_project(project.id)?;
+                        }
+                    }
                 },
                 ProjectCommands::Delete { project } => {
-                    let project_id = get_project_id_from_input_or_current(storage, project)?;
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
 
                     match project_id {
+                        Some(project_id) if storage.get_global_project()? == Some(project_id) => {
+                            io.cannot_delete_global_project();
+                        },
                         Some(project_id) => {
                             // unwrap is safe because project_id exists
                             let project = storage.get_project(project_id)?.unwrap();
 
-                            if io.confirm_delete_project(&project)? {
+                            if !config.for_project(&project.path)?.confirm || io.confirm_delete_project(&project)? {
                                 storage.delete_project(project_id)?;
                             }
                         },
                         None => io.project_not_found(),
                     }
-                }
+                },
+                ProjectCommands::Relink { project } => {
+                    let project_id = match project {
+                        Some(project) => find_project_by_dir_name(storage, io, &project)?,
+                        None => storage.get_marked_project()?,
+                    };
+
+                    match project_id {
+                        Some(project_id) if storage.get_global_project()? == Some(project_id) => {
+                            io.cannot_relink_global_project();
+                        },
+                        Some(project_id) => {
+                            if storage.relink_project(project_id)? {
+                                io.project_relinked();
+                            } else {
+                                io.directory_taken();
+                            }
+                        },
+                        None => io.project_not_found(),
+                    }
+                },
+            }
+        },
+        Commands::Template { command } => {
+            match command {
+                TemplateCommands::Save { name, anchor, project, global } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
+                    let config = &project_config(storage, config, project_id)?;
+                    match (project_id, parse_anchor(config, anchor.as_deref())) {
+                        (Some(project_id), Some(anchor)) => {
+                            storage.save_template(&name, &storage.get_tasks(project_id)?, anchor)?;
+                            io.template_saved(&name);
+                        },
+                        (None, _) => io.project_not_found(),
+                        (_, None) => io.invalid_time(),
+                    }
+                },
+                TemplateCommands::List => {
+                    io.list_templates(&storage.get_all_templates()?)?;
+                },
+                TemplateCommands::Delete { name } => {
+                    match storage.get_template(&name)? {
+                        Some(template) => storage.delete_template(template.id)?,
+                        None => io.template_not_found(),
+                    }
+                },
+                TemplateCommands::Apply { name, anchor, project, global } => {
+                    let project_id = get_or_create_project_id(storage, config, io, project, global)?;
+                    let config = &project_config(storage, config, project_id)?;
+                    match (project_id, storage.get_template(&name)?, parse_anchor(config, anchor.as_deref())) {
+                        (Some(project_id), _, _) if storage.is_project_archived(project_id)? => io.project_archived(),
+                        (Some(project_id), Some(template), Some(anchor)) => {
+                            for task in &template.tasks {
+                                storage.add_task(
+                                    project_id,
+                                    &task.name,
+                                    task.due_offset.map(|offset| anchor + offset),
+                                )?;
+                            }
+                        },
+                        (None, _, _) => io.project_not_found(),
+                        (_, None, _) => io.template_not_found(),
+                        (_, _, None) => io.invalid_time(),
+                    }
+                },
             }
         },
-        Commands::List { project, hide_finished } => {
-            let project_id = get_project_id_from_input_or_current(storage, project)?;
+        Commands::Workspace { command } => {
+            match command {
+                WorkspaceCommands::Create { name } => {
+                    if !storage.create_workspace(&name)? {
+                        io.workspace_already_exists();
+                    }
+                },
+                WorkspaceCommands::List => {
+                    io.list_workspaces(&storage.get_all_workspaces()?)?;
+                },
+                WorkspaceCommands::Delete { name } => {
+                    match storage.get_workspace(&name)? {
+                        Some(workspace) => storage.delete_workspace(workspace.id)?,
+                        None => io.workspace_not_found(),
+                    }
+                },
+                WorkspaceCommands::Add { workspace, project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match (storage.get_workspace(&workspace)?, project_id) {
+                        (Some(workspace), Some(project_id)) => storage.add_workspace_project(workspace.id, project_id)?,
+                        (None, _) => io.workspace_not_found(),
+                        (_, None) => io.project_not_found(),
+                    }
+                },
+                WorkspaceCommands::Remove { workspace, project } => {
+                    let project_id = get_project_id_from_input_or_current(storage, config, io, project, false)?;
+                    match (storage.get_workspace(&workspace)?, project_id) {
+                        (Some(workspace), Some(project_id)) => {
+                            if !storage.remove_workspace_project(workspace.id, project_id)? {
+                                io.project_not_in_workspace();
+                            }
+                        },
+                        (None, _) => io.workspace_not_found(),
+                        (_, None) => io.project_not_found(),
+                    }
+                },
+            }
+        },
+        Commands::Config { command } => {
+            match command {
+                ConfigCommands::Get { key } => {
+                    let config = current_config(storage, config)?;
+                    match config.get(&key) {
+                        Some(value) => io.print_config_value(&key, &value, &config.origin(&key))?,
+                        None => io.config_key_not_found(),
+                    }
+                },
+                ConfigCommands::Set { key, value } => {
+                    write_config_value(&key, &value)?;
+                },
+                ConfigCommands::List { show_origin } => {
+                    let config = current_config(storage, config)?;
+                    let entries = Config::value_keys()
+                        .into_iter()
+                        .filter_map(|key| {
+                            let value = config.get(&key)?;
+                            let origin = config.origin(&key);
+                            Some((key, value, origin))
+                        })
+                        .collect::<Vec<_>>();
+                    io.list_config(&entries, show_origin)?;
+                },
+            }
+        },
+        Commands::Profile { command } => {
+            let active = config.profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+            match command {
+                ProfileCommands::Create { name } => {
+                    if profile_exists(&name)? {
+                        io.profile_already_exists();
+                    } else {
+                        TaskStorage::new(&get_profile_path(&name)?)?;
+                        io.profile_created(&name);
+                    }
+                },
+                ProfileCommands::List => {
+                    io.list_profiles(&get_profiles()?, active)?;
+                },
+                ProfileCommands::Delete { name, no_confirm } => {
+                    if name == DEFAULT_PROFILE {
+                        io.cannot_delete_default_profile();
+                    } else if name == active {
+                        io.cannot_delete_active_profile();
+                    } else if !profile_exists(&name)? {
+                        io.profile_not_found();
+                    } else if no_confirm || !config.confirm || io.confirm_delete_profile(&name)? {
+                        delete_profile(&name)?;
+                    }
+                },
+            }
+        },
+        Commands::List { hide_finished, show_finished, workspace: Some(workspace), .. } => {
+            match storage.get_workspace(&workspace)? {
+                Some(workspace) => {
+                    let hide_override = finished_override(hide_finished, show_finished);
+                    let projects = workspace
+                        .projects
+                        .into_iter()
+                        .filter(|p| !p.archived)
+                        .map(|p| list_options(config, &p, hide_override).map(|options| (p, options)))
+                        .collect::<Result<Vec<_>>>()?;
+                    io.print_all_tasks(&projects)?;
+                },
+                None => io.workspace_not_found(),
+            }
+        },
+        Commands::List { hide_finished, show_finished, all: true, .. } => {
+            let hide_override = finished_override(hide_finished, show_finished);
+            let projects = get_all_projects(storage, sources, io)?
+                .into_iter()
+                .map(|p| list_options(config, &p, hide_override).map(|options| (p, options)))
+                .collect::<Result<Vec<_>>>()?;
+            io.print_all_tasks(&projects)?;
+        },
+        Commands::List { project, hide_finished, show_finished, all: false, recursive, global, workspace: None } => {
+            let hide_override = finished_override(hide_finished, show_finished);
+            let from_cwd = project.is_none() && !global;
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
             match project_id {
                 Some(project_id) => {
                     // unwrap is safe because project_id exists
                     let project = storage.get_project(project_id)?.unwrap();
-                    io.print_tasks(&project, hide_finished)?;
+                    let options = list_options(config, &project, hide_override)?;
+                    let sub_projects = match recursive {
+                        true => storage
+                            .get_sub_projects(project_id)?
+                            .into_iter()
+                            .filter(|p| !p.archived)
+                            .collect::<Vec<_>>(),
+                        false => Vec::new(),
+                    };
+                    let source_projects = get_source_projects(sources, config, io, &project, hide_override)?;
+                    io.print_project_tasks(&project, &options, &sub_projects, &source_projects)?;
                 },
-                None => io.project_not_found(),
+                None => match get_git_project(config)? {
+                    Some(project) if from_cwd => io.print_tasks(&project, &list_options(config, &project, hide_override)?)?,
+                    _ => io.project_not_found(),
+                },
+            }
+        },
+        Commands::Today { workspace } => {
+            let mut entries = get_tasks_due_between(storage, sources, io, start_of_day(0), start_of_day(1))?;
+            if retain_workspace(storage, io, workspace.as_deref(), &mut entries)? {
+                io.print_agenda(&entries)?;
+            }
+        },
+        Commands::Week { workspace } => {
+            let mut entries = get_tasks_due_between(storage, sources, io, start_of_day(0), start_of_day(7))?;
+            if retain_workspace(storage, io, workspace.as_deref(), &mut entries)? {
+                io.print_agenda(&entries)?;
+            }
+        },
+        Commands::Overdue { workspace } => {
+            let mut entries = get_tasks_due_between(storage, sources, io, i64::MIN, Utc::now().timestamp())?;
+            if retain_workspace(storage, io, workspace.as_deref(), &mut entries)? {
+                io.print_agenda(&entries)?;
             }
         },
-        Commands::Add { name, time, project } => {
-            let project_id = get_project_id_from_input_or_current(storage, project)?;
+        Commands::Calendar { month, project, list, global, workspace } => {
+            let first = match month {
+                Some(month) => parse_month(&month),
+                None => Local::now().date_naive().with_day(1),
+            };
+            let Some(first) = first else {
+                io.invalid_month();
+                return Ok(());
+            };
+            // unwrap is safe because the first of a month can always be advanced by one month
+            let next = first.checked_add_months(Months::new(1)).unwrap();
+
+            let mut entries = get_tasks_due_between(storage, sources, io, local_midnight(first), local_midnight(next))?;
+            if project.is_some() || global {
+                match get_project_id_from_input_or_current(storage, config, io, project, global)? {
+                    Some(project_id) => entries.retain(|e| e.source.is_none() && e.task.project_id == project_id),
+                    None => {
+                        io.project_not_found();
+                        return Ok(());
+                    },
+                }
+            }
+            if !retain_workspace(storage, io, workspace.as_deref(), &mut entries)? {
+                return Ok(());
+            }
+
+            io.print_calendar(first, &entries, list)?;
+        },
+        Commands::Add { name, time, project, global } => {
+            let project_id = get_or_create_project_id(storage, config, io, project, global)?;
             match project_id {
+                Some(project_id) if storage.is_project_archived(project_id)? => io.project_archived(),
                 Some(project_id) => {
+                    let config = project_config(storage, config, Some(project_id))?;
+                    let time = time.or(storage.get_project_settings(project_id)?.default_due);
                     storage.add_task(
                         project_id,
                         &name,
-                        time.as_deref(),
+                        time.as_deref().and_then(|time| parse_to_unix(time, config.date_order)),
                     )?;
                 },
                 None => io.project_not_found(),
             }
         },
-        Commands::Delete { number, no_confirm, project } => {
-            let project_id = get_project_id_from_input_or_current(storage, project)?;
+        Commands::Delete { number, no_confirm, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
             match project_id {
                 Some(project_id) => {
+                    let config = project_config(storage, config, Some(project_id))?;
                     // get number - 1 because task 0 is displayed as 1
-                    match storage.get_tasks(project_id)?.get(number - 1) {
+                    match storage.get_tasks(project_id)?.get(number.wrapping_sub(1)) {
                         Some(task) => {
-                            if no_confirm || io.confirm_delete_task(&storage.get_task(task.id)?)? {
+                            if no_confirm || !config.confirm || io.confirm_delete_task(&storage.get_task(task.id)?)? {
                                 storage.delete_task(task.id)?;
                             }
                         },
@@ -81,16 +688,17 @@ pub fn run(storage: &TaskStorage, io: &mut TaskIO, cli: Cli) -> Result<()> {
                 None => io.project_not_found(),
             }
         },
-        Commands::Edit { number, name, time, project } => {
-            let project_id = get_project_id_from_input_or_current(storage, project)?;
+        Commands::Edit { number, name, time, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
             match project_id {
                 Some(project_id) => {
+                    let config = project_config(storage, config, Some(project_id))?;
                     // get number - 1 because task 0 is displayed as 1
-                    match storage.get_tasks(project_id)?.get(number - 1) {
+                    match storage.get_tasks(project_id)?.get(number.wrapping_sub(1)) {
                         Some(task) => storage.update_task(
                             task.id,
                             name.as_deref(),
-                            time.as_deref().and_then(parse_to_unix),
+                            time.as_deref().and_then(|time| parse_to_unix(time, config.date_order)),
                         )?,
                         None => io.task_not_found(),
                     }
@@ -98,20 +706,160 @@ pub fn run(storage: &TaskStorage, io: &mut TaskIO, cli: Cli) -> Result<()> {
                 None => io.project_not_found(),
             }
         },
-        Commands::Finish { number, project } => {
-            let project_id = get_project_id_from_input_or_current(storage, project)?;
+        Commands::Finish { number, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
             match project_id {
                 Some(project_id) => {
                     // get number - 1 because task 0 is displayed as 1
-                    match storage.get_tasks(project_id)?.get(number - 1) {
+                    match storage.get_tasks(project_id)?.get(number.wrapping_sub(1)) {
                         Some(task) => storage.toggle_finish_task(task.id)?,
                         None => io.task_not_found(),
                     }
                 },
                 None => io.project_not_found(),
             }
+        },
+        Commands::Show { number, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
+            match project_id {
+                Some(project_id) => {
+                    // unwrap is safe because project_id exists
+                    let project = storage.get_project(project_id)?.unwrap();
+                    // get number - 1 because task 0 is displayed as 1
+                    match project.tasks.get(number.wrapping_sub(1)) {
+                        Some(task) => io.print_task_details(&project, number, task)?,
+                        None => io.task_not_found(),
+                    }
+                },
+                None => io.project_not_found(),
+            }
+        },
+        Commands::Search { query } => {
+            io.print_search_results(&search_tasks(storage, sources, io, &query)?)?;
+        },
+        Commands::Report { name } => {
+            let Some(report) = config.reports.iter().find(|r| r.name == name) else {
+                io.report_not_found(&config.reports);
+                return Ok(());
+            };
+
+            let mut entries = get_all_task_entries(storage, sources, io)?;
+            entries.retain(|e| report.filter.matches(e));
+            // the sorts are stable, so projects stay together in creation order
+            match report.sort {
+                TaskSort::Created => {},
+                TaskSort::Due => entries.sort_by_key(|e| (e.task.expiration.is_none(), e.task.expiration)),
+                TaskSort::Name => entries.sort_by_key(|e| e.task.name.to_lowercase()),
+            }
+            io.print_report(report, &entries)?;
+        },
+        Commands::Move { number, destination, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
+            let destination_id = find_project_by_dir_name(storage, io, &destination)?;
+            match (project_id, destination_id) {
+                (Some(_), Some(destination_id)) if storage.is_project_archived(destination_id)? => io.project_archived(),
+                (Some(project_id), Some(destination_id)) => {
+                    // get number - 1 because task 0 is displayed as 1
+                    match storage.get_tasks(project_id)?.get(number.wrapping_sub(1)) {
+                        Some(task) => storage.move_task(task.id, destination_id)?,
+                        None => io.task_not_found(),
+                    }
+                },
+                _ => io.project_not_found(),
+            }
+        },
+        Commands::Copy { number, destination, project, global } => {
+            let project_id = get_project_id_from_input_or_current(storage, config, io, project, global)?;
+            let destination_id = find_project_by_dir_name(storage, io, &destination)?;
+            match (project_id, destination_id) {
+                (Some(_), Some(destination_id)) if storage.is_project_archived(destination_id)? => io.project_archived(),
+                (Some(project_id), Some(destination_id)) => {
+                    // get number - 1 because task 0 is displayed as 1
+                    match storage.get_tasks(project_id)?.get(number.wrapping_sub(1)) {
+                        Some(task) => storage.copy_task(task.id, destination_id)?,
+                        None => io.task_not_found(),
+                    }
+                },
+                _ => io.project_not_found(),
+            }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::Theme;
+    use crate::storage::{MATCH_END, MATCH_START};
+    use rusqlite::Connection;
+
+    /// A database with the schema of the first release, before projects had
+    /// settings, identities or a search index.
+    fn baseline_source() -> Source {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects(
+                id INTEGER PRIMARY KEY,
+                path TEXT NOT NULL UNIQUE
+            );
+            CREATE TABLE tasks(
+                id INTEGER PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                finished INTEGER NOT NULL,
+                expiration INTEGER,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+            );
+            INSERT INTO projects (id, path) VALUES (1, '/srv/team');
+            INSERT INTO tasks (project_id, name, finished, expiration) VALUES (1, 'Team task', 0, 100);"
+        ).unwrap();
+
+        Source { name: "team".to_string(), storage: TaskStorage::from_connection(conn).unwrap() }
+    }
+
+    fn main_storage() -> TaskStorage {
+        let storage = TaskStorage::in_memory().unwrap();
+        let global = storage.get_global_project().unwrap().unwrap();
+        storage.add_task(global, "Own task", Some(200)).unwrap();
+        storage
+    }
+
+    #[test]
+    fn agenda_reads_sources_with_an_older_schema() {
+        let io = TaskIO::new(Theme::default(), false);
+        let entries = get_tasks_due_between(&main_storage(), &[baseline_source()], &io, i64::MIN, i64::MAX).unwrap();
+
+        assert_eq!(entries.iter().map(|e| e.task.name.as_str()).collect::<Vec<_>>(), vec!["Team task", "Own task"]);
+        assert_eq!(entries[0].source.as_deref(), Some("team"));
+        assert_eq!(entries[0].project_path, "/srv/team");
+    }
+
+    #[test]
+    fn search_reads_sources_without_a_search_index() {
+        let io = TaskIO::new(Theme::default(), false);
+        let results = search_tasks(&main_storage(), &[baseline_source()], &io, "TASK").unwrap();
+        assert_eq!(results.iter().map(|r| r.task.name.as_str()).collect::<Vec<_>>(), vec!["Own task", "Team task"]);
+
+        let results = search_tasks(&main_storage(), &[baseline_source()], &io, "task te").unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].snippet, format!("{MATCH_START}Te{MATCH_END}am {MATCH_START}task{MATCH_END}"));
+        assert!(search_tasks(&main_storage(), &[baseline_source()], &io, "100%").unwrap().is_empty());
+    }
+
+    #[test]
+    fn listings_read_sources_with_an_older_schema() {
+        let io = TaskIO::new(Theme::default(), false);
+        let storage = main_storage();
+        let sources = [baseline_source()];
+
+        let projects = get_all_projects(&storage, &sources, &io).unwrap();
+        assert_eq!(projects.iter().map(|p| p.path.as_str()).collect::<Vec<_>>(), vec!["global", "/srv/team"]);
+        assert_eq!(get_all_task_entries(&storage, &sources, &io).unwrap().len(), 2);
+
+        let team = Project { path: "/srv/team".to_string(), ..Default::default() };
+        let source_projects = get_source_projects(&sources, &Config::default(), &io, &team, None).unwrap();
+        assert_eq!(source_projects[0].0.tasks[0].name, "Team task");
+    }
+}
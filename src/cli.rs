@@ -1,20 +1,156 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "task")]
 #[command(about = "Simple CLI task manager")]
 pub struct Cli {
+    #[arg(long, global = true)]
+    pub db: Option<PathBuf>,
+    #[arg(long, global = true, conflicts_with = "db")]
+    pub profile: Option<String>,
+    #[arg(long, global = true, value_parser = ["auto", "always", "never"])]
+    pub color: Option<String>,
+    #[arg(long, global = true, action = clap::ArgAction::SetTrue)]
+    pub json: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
 
 #[derive(Subcommand)]
 pub enum ProjectCommands {
-    New,
+    New {
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        marker: bool,
+    },
+    List {
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        archived: bool,
+    },
+    Delete {
+        project: Option<String>,
+    },
+    Archive {
+        project: Option<String>,
+    },
+    Unarchive {
+        project: Option<String>,
+    },
+    Relink {
+        project: Option<String>,
+    },
+    Set {
+        key: String,
+        value: String,
+        project: Option<String>,
+    },
+    Unset {
+        key: String,
+        project: Option<String>,
+    },
+    Info {
+        project: Option<String>,
+    },
+    Move {
+        project: String,
+        path: String,
+    },
+    Rename {
+        name: String,
+        project: Option<String>,
+    },
+    Alias {
+        #[command(subcommand)]
+        command: AliasCommands,
+    },
+    Prune {
+        #[arg(short, long)]
+        reassign: Option<String>,
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_confirm: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum AliasCommands {
+    Add {
+        alias: String,
+        project: Option<String>,
+    },
+    Remove {
+        alias: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommands {
+    Save {
+        name: String,
+        #[arg(short, long)]
+        anchor: Option<String>,
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+    },
+    List,
+    Delete {
+        name: String,
+    },
+    Apply {
+        name: String,
+        #[arg(short, long)]
+        anchor: Option<String>,
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorkspaceCommands {
+    Create {
+        name: String,
+    },
     List,
     Delete {
+        name: String,
+    },
+    Add {
+        workspace: String,
         project: Option<String>,
     },
+    Remove {
+        workspace: String,
+        project: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    Get {
+        key: String,
+    },
+    Set {
+        key: String,
+        value: String,
+    },
+    List {
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        show_origin: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    Create {
+        name: String,
+    },
+    List,
+    Delete {
+        name: String,
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        no_confirm: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -23,22 +159,76 @@ pub enum Commands {
         #[command(subcommand)]
         command: ProjectCommands,
     },
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommands,
+    },
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommands,
+    },
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
     List {
+        #[arg(conflicts_with = "all")]
         project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with_all = ["project", "all"])]
+        global: bool,
         #[arg(long, action = clap::ArgAction::SetTrue)]
         hide_finished: bool,
+        #[arg(long, action = clap::ArgAction::SetTrue, conflicts_with = "hide_finished")]
+        show_finished: bool,
+        #[arg(long, action = clap::ArgAction::SetTrue)]
+        all: bool,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "all")]
+        recursive: bool,
+        #[arg(short, long, conflicts_with_all = ["project", "global", "all", "recursive"])]
+        workspace: Option<String>,
+    },
+    Today {
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    Week {
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    Overdue {
+        #[arg(short, long)]
+        workspace: Option<String>,
+    },
+    Calendar {
+        month: Option<String>,
+        #[arg(short, long)]
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+        #[arg(short, long, action = clap::ArgAction::SetTrue)]
+        list: bool,
+        #[arg(short, long, conflicts_with_all = ["project", "global"])]
+        workspace: Option<String>,
     },
     Add {
         name: String,
         #[arg(short, long)]
         time: Option<String>,
         project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
     },
     Delete {
         number: usize,
         #[arg(long, action = clap::ArgAction::SetTrue)]
         no_confirm: bool,
         project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
     },
     Edit {
         number: usize,
@@ -47,9 +237,39 @@ pub enum Commands {
         #[arg(short, long)]
         time: Option<String>,
         project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
     },
     Finish {
         number: usize,
         project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+    },
+    Show {
+        number: usize,
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+    },
+    Search {
+        query: String,
+    },
+    Report {
+        name: String,
+    },
+    Move {
+        number: usize,
+        destination: String,
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
+    },
+    Copy {
+        number: usize,
+        destination: String,
+        project: Option<String>,
+        #[arg(short, long, action = clap::ArgAction::SetTrue, conflicts_with = "project")]
+        global: bool,
     },
 }
@@ -0,0 +1,775 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml_edit::{Document, DocumentMut, Item, TableLike, Value};
+
+use chrono::Utc;
+
+use crate::io::Theme;
+use crate::storage::{DEFAULT_PROFILE, profile_exists};
+use crate::types::AgendaEntry;
+use crate::utils::{is_valid_profile_name, parse_duration, project_label};
+
+/// Name of the file in a project directory that overrides the user configuration.
+pub const PROJECT_CONFIG_FILE: &str = ".taskrc.toml";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GitProjects {
+    /// Only registered projects are used.
+    Off,
+    /// Directories inside an unregistered git work tree resolve to its root.
+    Resolve,
+    /// Like `Resolve`, and the root is registered as a project on first write.
+    Create,
+}
+
+/// Order of day, month and year in absolute dates such as `2410`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    Dmy,
+    Mdy,
+    Ymd,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+/// Order of tasks within a project listing. Task numbers are not affected.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TaskSort {
+    Created,
+    Due,
+    Name,
+}
+
+/// An additional database listed under `databases`, read alongside the main one.
+#[derive(Clone)]
+pub struct ExtraDatabase {
+    pub name: String,
+    pub path: PathBuf,
+    /// Opened without write access and without upgrading its schema.
+    pub read_only: bool,
+}
+
+/// A task listing defined under `[reports.<name>]`, run with `task report <name>`.
+#[derive(Clone)]
+pub struct Report {
+    pub name: String,
+    pub columns: Vec<ReportColumn>,
+    pub filter: ReportFilter,
+    pub sort: TaskSort,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ReportColumn {
+    /// Number of the task within its project, as used by `task edit`.
+    Number,
+    Name,
+    DueRelative,
+    DueAbsolute,
+    Status,
+    Project,
+}
+
+impl ReportColumn {
+    pub const NAMES: [&str; 6] = ["number", "name", "due-relative", "due-absolute", "status", "project"];
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "number" => Some(Self::Number),
+            "name" => Some(Self::Name),
+            "due-relative" => Some(Self::DueRelative),
+            "due-absolute" => Some(Self::DueAbsolute),
+            "status" => Some(Self::Status),
+            "project" => Some(Self::Project),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum DueFilter {
+    None,
+    Any,
+    Overdue,
+    /// Due within this many seconds from now, including overdue tasks.
+    Within(i64),
+}
+
+/// Terms a task must all match to be part of a report, written as e.g.
+/// `status:pending due:3d project:web docs`. Terms other than `status:`,
+/// `due:` and `project:` match the task name.
+#[derive(Clone, Default)]
+pub struct ReportFilter {
+    finished: Option<bool>,
+    due: Option<DueFilter>,
+    projects: Vec<String>,
+    words: Vec<String>,
+}
+
+impl ReportFilter {
+    fn parse(filter: &str) -> Result<Self> {
+        let mut parsed = Self::default();
+        for term in filter.split_whitespace() {
+            match term.split_once(':') {
+                Some(("status", status)) => {
+                    parsed.finished = match status {
+                        "pending" => Some(false),
+                        "done" => Some(true),
+                        _ => bail!("Invalid filter `{}`, status must be pending or done", term),
+                    };
+                },
+                Some(("due", due)) => {
+                    parsed.due = Some(match due {
+                        "none" => DueFilter::None,
+                        "any" => DueFilter::Any,
+                        "overdue" => DueFilter::Overdue,
+                        _ => match parse_duration(due) {
+                            Some(duration) => DueFilter::Within(duration.num_seconds()),
+                            None => bail!("Invalid filter `{}`, due must be none, any, overdue or a duration such as `3d`", term),
+                        },
+                    });
+                },
+                Some(("project", project)) => parsed.projects.push(project.to_lowercase()),
+                // likely a misspelled key, which would otherwise silently match nothing
+                Some((key, value)) if is_filter_key(key) && !value.starts_with("//") => {
+                    bail!("Invalid filter `{}`, unknown key `{}`, expected status, due or project", term, key);
+                },
+                // other terms such as `https://example.com` or `10:30` are matched as words
+                _ => parsed.words.push(term.to_lowercase()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    pub fn matches(&self, entry: &AgendaEntry) -> bool {
+        let task = &entry.task;
+        let now = Utc::now().timestamp();
+        let due_matches = match (self.due, task.expiration) {
+            (None, _) => true,
+            (Some(DueFilter::None), expiration) => expiration.is_none(),
+            (Some(_), None) => false,
+            (Some(DueFilter::Any), Some(_)) => true,
+            (Some(DueFilter::Overdue), Some(expiration)) => expiration <= now,
+            (Some(DueFilter::Within(seconds)), Some(expiration)) => expiration <= now + seconds,
+        };
+        let project = project_label(entry.project_name.as_deref(), &entry.project_path).to_lowercase();
+        let name = task.name.to_lowercase();
+
+        self.finished.is_none_or(|finished| task.finished == finished)
+            && due_matches
+            && self.projects.iter().all(|p| project.contains(p.as_str()))
+            && self.words.iter().all(|word| name.contains(word.as_str()))
+    }
+}
+
+/// Whether the text before the colon of a filter term looks like a key, as
+/// opposed to part of a word such as a time.
+fn is_filter_key(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic()) && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Where the effective value of a configuration key came from.
+#[derive(Clone)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(&'static str),
+    Argument(&'static str),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(path) => write!(f, "{}", path.display()),
+            Origin::Env(name) => write!(f, "environment variable {}", name),
+            Origin::Argument(name) => write!(f, "command line option {}", name),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    /// Use the global project when the current directory has no project.
+    pub global_fallback: bool,
+    pub git_projects: GitProjects,
+    pub date_order: DateOrder,
+    /// Hide finished tasks in listings of projects without a `hide_finished` setting.
+    pub hide_finished: bool,
+    /// Ask before deleting; `false` behaves as if `--no-confirm` was always given.
+    pub confirm: bool,
+    pub color: ColorMode,
+    pub sort: TaskSort,
+    /// Profile whose database is used, see `storage::get_storage_path`.
+    pub profile: Option<String>,
+    pub databases: Vec<ExtraDatabase>,
+    pub theme: Theme,
+    pub reports: Vec<Report>,
+    origins: HashMap<String, Origin>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            global_fallback: false,
+            git_projects: GitProjects::Off,
+            date_order: DateOrder::Dmy,
+            hide_finished: false,
+            confirm: true,
+            color: ColorMode::Auto,
+            sort: TaskSort::Created,
+            profile: None,
+            databases: Vec::new(),
+            theme: Theme::default(),
+            reports: Vec::new(),
+            origins: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Top-level keys. `theme` is a table whose keys are `Theme::KEYS`, and
+    /// `reports` a table of reports.
+    pub const KEYS: [&str; 11] = [
+        "global_fallback",
+        "git_projects",
+        "date_order",
+        "hide_finished",
+        "confirm",
+        "color",
+        "sort",
+        "profile",
+        "databases",
+        "theme",
+        "reports",
+    ];
+
+    /// Keys that a project's `.taskrc.toml` may override.
+    pub const PROJECT_KEYS: [&str; 4] = ["date_order", "hide_finished", "confirm", "sort"];
+
+    /// Reads the configuration file, if any. `TASK_GLOBAL_FALLBACK` and
+    /// `TASK_GIT_PROJECTS` take precedence over the file.
+    pub fn load() -> Result<Self> {
+        let mut config = Self::default();
+        // without a config directory there is no file to read
+        if let Ok(path) = config_path() {
+            config.apply_file(&path, &Self::KEYS)?;
+        }
+
+        if let Ok(value) = std::env::var("TASK_GLOBAL_FALLBACK") {
+            config.global_fallback = matches!(value.to_lowercase().as_str(), "1" | "true" | "yes");
+            config.origins.insert("global_fallback".to_string(), Origin::Env("TASK_GLOBAL_FALLBACK"));
+        }
+        if let Ok(value) = std::env::var("TASK_GIT_PROJECTS") {
+            config.set("git_projects", &Value::from(value.to_lowercase()))
+                .context("Invalid value in TASK_GIT_PROJECTS")?;
+            config.origins.insert("git_projects".to_string(), Origin::Env("TASK_GIT_PROJECTS"));
+        }
+        if let Ok(value) = std::env::var("TASK_PROFILE") {
+            config.set("profile", &Value::from(value))
+                .context("Invalid value in TASK_PROFILE")?;
+            config.origins.insert("profile".to_string(), Origin::Env("TASK_PROFILE"));
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the configuration with the `.taskrc.toml` in the directory of
+    /// the project at `project_path` layered on top.
+    pub fn for_project(&self, project_path: &str) -> Result<Self> {
+        let mut config = self.clone();
+        let project_path = Path::new(project_path);
+        // the global project has no directory
+        if project_path.is_absolute() {
+            config.apply_file(&project_path.join(PROJECT_CONFIG_FILE), &Self::PROJECT_KEYS)?;
+        }
+        Ok(config)
+    }
+
+    /// Every key that has a value, with the keys of `theme` spelled out.
+    pub fn value_keys() -> Vec<String> {
+        Self::KEYS
+            .iter()
+            .flat_map(|key| match *key {
+                "theme" => Theme::KEYS.iter().map(|theme_key| format!("theme.{theme_key}")).collect(),
+                key => vec![key.to_string()],
+            })
+            .collect()
+    }
+
+    /// Applies `--color` given on the command line.
+    pub fn override_color(&mut self, color: &str) -> Result<()> {
+        self.set("color", &Value::from(color))?;
+        self.origins.insert("color".to_string(), Origin::Argument("--color"));
+        Ok(())
+    }
+
+    /// Selects a profile given on the command line.
+    pub fn override_profile(&mut self, profile: &str) -> Result<()> {
+        self.set("profile", &Value::from(profile))?;
+        self.origins.insert("profile".to_string(), Origin::Argument("--profile"));
+        Ok(())
+    }
+
+    pub fn origin(&self, key: &str) -> Origin {
+        self.origins.get(key).cloned().unwrap_or(Origin::Default)
+    }
+
+    fn apply_file(&mut self, path: &Path, allowed: &[&str]) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        self.apply_document(&text, path, allowed)
+            .with_context(|| format!("Invalid configuration in {}", path.display()))
+    }
+
+    fn apply_document(&mut self, text: &str, path: &Path, allowed: &[&str]) -> Result<()> {
+        let line_of = |item: &Item| item.span().map_or(0, |span| text[..span.start].matches('\n').count() + 1);
+
+        let document = Document::parse(text)?;
+        for (key, item) in document.iter() {
+            let line = line_of(item);
+            if Self::KEYS.contains(&key) && !allowed.contains(&key) {
+                bail!("line {}: `{}` cannot be set per project, only {} can", line, key, allowed.join(", "));
+            }
+            if let Some(tables) = item.as_array_of_tables().filter(|_| key == "databases") {
+                self.databases = tables
+                    .iter()
+                    .map(|table| parse_database(table))
+                    .collect::<Result<_>>()?;
+                check_database_names(&self.databases)?;
+                self.origins.insert(key.to_string(), Origin::File(path.to_path_buf()));
+                continue;
+            }
+            if key == "theme" {
+                let Some(table) = item.as_table_like() else {
+                    bail!("line {}: `theme` must be a table", line);
+                };
+                for (name, item) in table.iter() {
+                    let key = format!("theme.{name}");
+                    let Some(value) = item.as_value() else {
+                        bail!("line {}: `{}` must be a value, not a table", line_of(item), key);
+                    };
+                    self.set(&key, value).map_err(|error| anyhow!("line {}: {}", line_of(item), error))?;
+                    self.origins.insert(key, Origin::File(path.to_path_buf()));
+                }
+                continue;
+            }
+            if key == "reports" {
+                let Some(table) = item.as_table_like() else {
+                    bail!("line {}: `reports` must be a table of reports", line);
+                };
+                self.reports = table
+                    .iter()
+                    .map(|(name, item)| match item.as_table_like() {
+                        Some(report) => parse_report(name, report, &line_of),
+                        None => bail!("line {}: report `{}` must be a table", line_of(item), name),
+                    })
+                    .collect::<Result<_>>()?;
+                self.origins.insert(key.to_string(), Origin::File(path.to_path_buf()));
+                continue;
+            }
+            let Some(value) = item.as_value() else {
+                bail!("line {}: `{}` must be a value, not a table", line, key);
+            };
+            self.set(key, value).map_err(|error| anyhow!("line {}: {}", line, error))?;
+            self.origins.insert(key.to_string(), Origin::File(path.to_path_buf()));
+        }
+        Ok(())
+    }
+
+    /// Sets `key` to `value`, rejecting unknown keys and values of the wrong type.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<()> {
+        if let Some(theme_key) = key.strip_prefix("theme.") {
+            let Some(spec) = value.as_str() else {
+                bail!("`{}` must be a string, found {}", key, value.to_string().trim());
+            };
+            return self.theme.set(theme_key, spec);
+        }
+
+        match key {
+            "global_fallback" => self.global_fallback = expect_bool(key, value)?,
+            "hide_finished" => self.hide_finished = expect_bool(key, value)?,
+            "confirm" => self.confirm = expect_bool(key, value)?,
+            "git_projects" => {
+                self.git_projects = match expect_str(key, value, &["off", "resolve", "create"])? {
+                    "resolve" => GitProjects::Resolve,
+                    "create" => GitProjects::Create,
+                    _ => GitProjects::Off,
+                };
+            },
+            "date_order" => {
+                self.date_order = match expect_str(key, value, &["dmy", "mdy", "ymd"])? {
+                    "mdy" => DateOrder::Mdy,
+                    "ymd" => DateOrder::Ymd,
+                    _ => DateOrder::Dmy,
+                };
+            },
+            "color" => {
+                self.color = match expect_str(key, value, &["auto", "always", "never"])? {
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    _ => ColorMode::Auto,
+                };
+            },
+            "sort" => {
+                self.sort = match expect_str(key, value, &["created", "due", "name"])? {
+                    "due" => TaskSort::Due,
+                    "name" => TaskSort::Name,
+                    _ => TaskSort::Created,
+                };
+            },
+            "profile" => {
+                let Some(profile) = value.as_str() else {
+                    bail!("`{}` must be a string, found {}", key, value.to_string().trim());
+                };
+                if !profile.is_empty() && !is_valid_profile_name(profile) {
+                    bail!("`{}` must only contain letters, digits, '-' and '_', found {}", key, value.to_string().trim());
+                }
+                self.profile = Some(profile.to_string()).filter(|profile| !profile.is_empty());
+            },
+            "databases" => {
+                let tables = value
+                    .as_array()
+                    .map(|array| array.iter().map(|value| value.as_inline_table()).collect::<Option<Vec<_>>>());
+                let Some(Some(tables)) = tables else {
+                    bail!("`{}` must be an array of tables with `name` and `path`", key);
+                };
+                self.databases = tables
+                    .into_iter()
+                    .map(|table| parse_database(table))
+                    .collect::<Result<_>>()?;
+                check_database_names(&self.databases)?;
+            },
+            "theme" => bail!("`theme` is a table, set its keys such as `theme.overdue` instead"),
+            "reports" => bail!("`reports` is a table, define reports as `[reports.<name>]` in the configuration file"),
+            _ => bail!("Unknown key `{}`, expected one of: {}", key, Self::KEYS.join(", ")),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        if let Some(theme_key) = key.strip_prefix("theme.") {
+            return self.theme.get(theme_key);
+        }
+
+        let value = match key {
+            "global_fallback" => self.global_fallback.to_string(),
+            "hide_finished" => self.hide_finished.to_string(),
+            "confirm" => self.confirm.to_string(),
+            "git_projects" => match self.git_projects {
+                GitProjects::Off => "off",
+                GitProjects::Resolve => "resolve",
+                GitProjects::Create => "create",
+            }.to_string(),
+            "date_order" => match self.date_order {
+                DateOrder::Dmy => "dmy",
+                DateOrder::Mdy => "mdy",
+                DateOrder::Ymd => "ymd",
+            }.to_string(),
+            "color" => match self.color {
+                ColorMode::Auto => "auto",
+                ColorMode::Always => "always",
+                ColorMode::Never => "never",
+            }.to_string(),
+            "sort" => match self.sort {
+                TaskSort::Created => "created",
+                TaskSort::Due => "due",
+                TaskSort::Name => "name",
+            }.to_string(),
+            "profile" => self.profile.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+            "databases" if self.databases.is_empty() => "none".to_string(),
+            "databases" => self.databases
+                .iter()
+                .map(|database| format!(
+                    "{} ({}{})",
+                    database.name,
+                    database.path.display(),
+                    if database.read_only { ", read-only" } else { "" },
+                ))
+                .collect::<Vec<_>>()
+                .join(", "),
+            "reports" if self.reports.is_empty() => "none".to_string(),
+            "reports" => self.reports.iter().map(|report| report.name.as_str()).collect::<Vec<_>>().join(", "),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+/// Validates `value` for `key` and writes it to the configuration file,
+/// keeping the formatting and comments of the rest of the file.
+pub fn write_config_value(key: &str, value: &str) -> Result<()> {
+    // values that are not valid TOML, such as `due`, are taken as strings
+    let value = value.parse::<Value>().unwrap_or_else(|_| Value::from(value));
+    Config::default().set(key, &value)?;
+    // checked here rather than in `set`, so that a configuration naming a
+    // deleted profile can still be loaded and changed
+    if let Some(profile) = value.as_str().filter(|profile| key == "profile" && !profile.is_empty())
+        && !profile_exists(profile)?
+    {
+        bail!("Profile '{}' does not exist, create it with `task profile create {}`", profile, profile);
+    }
+
+    let path = config_path()?;
+    let mut document = match std::fs::read_to_string(&path) {
+        Ok(text) => text.parse::<DocumentMut>()
+            .with_context(|| format!("Invalid configuration in {}", path.display()))?,
+        Err(_) => DocumentMut::new(),
+    };
+    match key.split_once('.') {
+        Some((table, key)) => {
+            if !document.contains_key(table) {
+                document[table] = toml_edit::table();
+            }
+            document[table][key] = toml_edit::value(value);
+        },
+        None => document[key] = toml_edit::value(value),
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, document.to_string())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(())
+}
+
+/// `TASK_CONFIG` if set, otherwise `config.toml` in the `task` config directory.
+pub fn config_path() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var("TASK_CONFIG") {
+        return Ok(PathBuf::from(path));
+    }
+    let base_dir = match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => dirs::config_dir().context("Could not determine the configuration directory")?,
+    };
+    Ok(base_dir.join("task").join("config.toml"))
+}
+
+fn parse_database(table: &dyn TableLike) -> Result<ExtraDatabase> {
+    let field = |name: &str| table.get(name).and_then(|item| item.as_value());
+
+    let Some(name) = field("name").and_then(|value| value.as_str()).filter(|name| !name.is_empty()) else {
+        bail!("Every entry of `databases` needs a `name`");
+    };
+    let Some(path) = field("path").and_then(|value| value.as_str()) else {
+        bail!("Database '{}' needs a `path`", name);
+    };
+    let read_only = match field("read_only") {
+        Some(value) => expect_bool("read_only", value)?,
+        None => true,
+    };
+    if let Some((key, _)) = table.iter().find(|(key, _)| !["name", "path", "read_only"].contains(key)) {
+        bail!("Unknown key `{}` in database '{}', expected name, path or read_only", key, name);
+    }
+
+    // `~/` is expanded since shared mounts are often referred to from the home directory
+    let path = match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    };
+
+    Ok(ExtraDatabase { name: name.to_string(), path, read_only })
+}
+
+/// Reads a report table, reporting errors with the line of the offending key.
+fn parse_report(name: &str, table: &dyn TableLike, line_of: &dyn Fn(&Item) -> usize) -> Result<Report> {
+    let mut report = Report {
+        name: name.to_string(),
+        columns: vec![ReportColumn::Number, ReportColumn::Name, ReportColumn::DueRelative],
+        filter: ReportFilter::default(),
+        sort: TaskSort::Created,
+    };
+    for (key, item) in table.iter() {
+        set_report_key(&mut report, key, item).map_err(|error| anyhow!("line {}: {}", line_of(item), error))?;
+    }
+    Ok(report)
+}
+
+fn set_report_key(report: &mut Report, key: &str, item: &Item) -> Result<()> {
+    let name = report.name.clone();
+    let Some(value) = item.as_value() else {
+        bail!("`{}` of report '{}' must be a value, not a table", key, name);
+    };
+    match key {
+        "columns" => {
+            let columns = value
+                .as_array()
+                .and_then(|array| array.iter().map(|value| value.as_str().and_then(ReportColumn::parse)).collect::<Option<Vec<_>>>())
+                .filter(|columns| !columns.is_empty());
+            let Some(columns) = columns else {
+                bail!("`columns` of report '{}' must be a list of: {}", name, ReportColumn::NAMES.join(", "));
+            };
+            report.columns = columns;
+        },
+        "filter" => {
+            let Some(filter) = value.as_str() else {
+                bail!("`filter` of report '{}' must be a string", name);
+            };
+            report.filter = ReportFilter::parse(filter)?;
+        },
+        "sort" => {
+            report.sort = match expect_str("sort", value, &["created", "due", "name"])? {
+                "due" => TaskSort::Due,
+                "name" => TaskSort::Name,
+                _ => TaskSort::Created,
+            };
+        },
+        _ => bail!("Unknown key `{}` in report '{}', expected columns, filter or sort", key, name),
+    }
+    Ok(())
+}
+
+fn check_database_names(databases: &[ExtraDatabase]) -> Result<()> {
+    for (index, database) in databases.iter().enumerate() {
+        if databases[..index].iter().any(|other| other.name == database.name) {
+            bail!("Database name '{}' is used more than once", database.name);
+        }
+    }
+    Ok(())
+}
+
+fn expect_bool(key: &str, value: &Value) -> Result<bool> {
+    match value.as_bool() {
+        Some(value) => Ok(value),
+        None => bail!("`{}` must be true or false, found {}", key, value.to_string().trim()),
+    }
+}
+
+fn expect_str<'a>(key: &str, value: &'a Value, allowed: &[&str]) -> Result<&'a str> {
+    match value.as_str() {
+        Some(value) if allowed.contains(&value) => Ok(value),
+        _ => bail!("`{}` must be one of {}, found {}", key, allowed.join(", "), value.to_string().trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Task;
+
+    fn entry(name: &str, finished: bool, due_in: Option<i64>) -> AgendaEntry {
+        AgendaEntry {
+            project_path: "/home/user/web".to_string(),
+            project_name: Some("Website".to_string()),
+            project_color: None,
+            number: 1,
+            task: Task {
+                id: 1,
+                project_id: 1,
+                name: name.to_string(),
+                finished,
+                expiration: due_in.map(|seconds| Utc::now().timestamp() + seconds),
+            },
+            source: None,
+        }
+    }
+
+    fn load(text: &str) -> Result<Config> {
+        let mut config = Config::default();
+        config.apply_document(text, Path::new("config.toml"), &Config::KEYS)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn filter_matches_status() {
+        let pending = ReportFilter::parse("status:pending").unwrap();
+        assert!(pending.matches(&entry("Deploy", false, None)));
+        assert!(!pending.matches(&entry("Deploy", true, None)));
+
+        let done = ReportFilter::parse("status:done").unwrap();
+        assert!(done.matches(&entry("Deploy", true, None)));
+        assert!(ReportFilter::parse("status:open").is_err());
+    }
+
+    #[test]
+    fn due_within_includes_overdue_tasks() {
+        let filter = ReportFilter::parse("due:3d").unwrap();
+        assert!(filter.matches(&entry("Overdue", false, Some(-3600))));
+        assert!(filter.matches(&entry("Soon", false, Some(2 * 24 * 3600))));
+        assert!(!filter.matches(&entry("Later", false, Some(4 * 24 * 3600))));
+        assert!(!filter.matches(&entry("Undated", false, None)));
+    }
+
+    #[test]
+    fn filter_matches_due_keywords() {
+        let overdue = ReportFilter::parse("due:overdue").unwrap();
+        assert!(overdue.matches(&entry("Overdue", false, Some(-60))));
+        assert!(!overdue.matches(&entry("Soon", false, Some(60))));
+
+        let none = ReportFilter::parse("due:none").unwrap();
+        assert!(none.matches(&entry("Undated", false, None)));
+        assert!(!none.matches(&entry("Soon", false, Some(60))));
+
+        let any = ReportFilter::parse("due:any").unwrap();
+        assert!(any.matches(&entry("Soon", false, Some(60))));
+        assert!(!any.matches(&entry("Undated", false, None)));
+
+        assert!(ReportFilter::parse("due:someday").is_err());
+    }
+
+    #[test]
+    fn filter_matches_project_and_words_ignoring_case() {
+        let filter = ReportFilter::parse("project:website DEPLOY").unwrap();
+        assert!(filter.matches(&entry("Deploy the release", false, None)));
+        assert!(!filter.matches(&entry("Write docs", false, None)));
+        assert!(!ReportFilter::parse("project:api").unwrap().matches(&entry("Deploy", false, None)));
+    }
+
+    #[test]
+    fn filter_matches_other_terms_with_a_colon_as_words() {
+        let filter = ReportFilter::parse("https://example.com").unwrap();
+        assert!(filter.matches(&entry("Check https://example.com/status", false, None)));
+        assert!(!filter.matches(&entry("Check the status", false, None)));
+    }
+
+    #[test]
+    fn filter_rejects_unknown_keys() {
+        let error = ReportFilter::parse("stauts:done").err().unwrap().to_string();
+        assert!(error.contains("unknown key `stauts`"), "{}", error);
+        assert!(ReportFilter::parse("note:").is_err());
+
+        let filter = ReportFilter::parse("10:30").unwrap();
+        assert!(filter.matches(&entry("Standup at 10:30", false, None)));
+    }
+
+    #[test]
+    fn reports_default_to_number_name_and_due() {
+        let config = load("[reports.all]\n").unwrap();
+        let report = &config.reports[0];
+        assert_eq!(report.name, "all");
+        assert!(report.columns == [ReportColumn::Number, ReportColumn::Name, ReportColumn::DueRelative]);
+        assert!(report.sort == TaskSort::Created);
+    }
+
+    #[test]
+    fn reports_read_columns_filter_and_sort() {
+        let config = load(
+            "[reports.soon]\ncolumns = [\"project\", \"name\", \"due-absolute\"]\nfilter = \"status:pending\"\nsort = \"due\"\n",
+        ).unwrap();
+        let report = &config.reports[0];
+        assert!(report.columns == [ReportColumn::Project, ReportColumn::Name, ReportColumn::DueAbsolute]);
+        assert!(report.sort == TaskSort::Due);
+        assert!(!report.filter.matches(&entry("Deploy", true, None)));
+    }
+
+    #[test]
+    fn reports_reject_invalid_columns_and_sort() {
+        let error = load("[reports.x]\ncolumns = [\"name\", \"tags\"]\n").err().unwrap();
+        assert!(error.to_string().contains("line 2"));
+        assert!(load("[reports.x]\ncolumns = []\n").is_err());
+        assert!(load("[reports.x]\ncolumns = \"name\"\n").is_err());
+        assert!(load("[reports.x]\nsort = \"priority\"\n").is_err());
+        assert!(load("[reports.x]\ngroup = \"project\"\n").is_err());
+    }
+}
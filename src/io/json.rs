@@ -0,0 +1,295 @@
+use chrono::{Local, SecondsFormat, TimeZone};
+use serde::Serialize;
+use serde::ser::{SerializeMap, Serializer};
+
+use crate::types::{AgendaEntry, Project, ProjectSettings, SearchResult, Task, Template, Workspace};
+use crate::utils::project_label;
+
+/// Version of the `--json` output, raised when fields are removed or change meaning.
+pub const VERSION: u32 = 1;
+
+/// Prints `{"version": VERSION, key: value}`.
+pub fn print(key: &str, value: impl Serialize) -> anyhow::Result<()> {
+    println!("{}", render(key, value)?);
+    Ok(())
+}
+
+fn render(key: &str, value: impl Serialize) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Document { key, value })
+}
+
+struct Document<'a, T> {
+    key: &'a str,
+    value: T,
+}
+
+impl<T: Serialize> Serialize for Document<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("version", &VERSION)?;
+        map.serialize_entry(self.key, &self.value)?;
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonTask<'a> {
+    /// Number of the task within its project, as shown by `task list`.
+    number: usize,
+    id: u32,
+    name: &'a str,
+    finished: bool,
+    /// RFC 3339 timestamp in local time.
+    due: Option<String>,
+}
+
+impl<'a> JsonTask<'a> {
+    pub fn new(number: usize, task: &'a Task) -> Self {
+        Self {
+            number,
+            id: task.id,
+            name: &task.name,
+            finished: task.finished,
+            due: task.expiration.and_then(iso_timestamp),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonProject<'a> {
+    id: u32,
+    path: &'a str,
+    name: Option<&'a str>,
+    label: String,
+    aliases: &'a [String],
+    archived: bool,
+    missing: bool,
+    parent_id: Option<u32>,
+    /// Name of the additional database the project was read from.
+    source: Option<&'a str>,
+    settings: &'a ProjectSettings,
+    tasks: Vec<JsonTask<'a>>,
+}
+
+impl<'a> JsonProject<'a> {
+    pub fn new(project: &'a Project, tasks: Vec<JsonTask<'a>>) -> Self {
+        Self {
+            id: project.id,
+            path: &project.path,
+            name: project.name.as_deref(),
+            label: project.label(),
+            aliases: &project.aliases,
+            archived: project.archived,
+            missing: project.is_missing(),
+            parent_id: project.parent_id,
+            source: project.source.as_deref(),
+            settings: &project.settings,
+            tasks,
+        }
+    }
+}
+
+/// A task of output covering several projects, with the project it belongs to.
+#[derive(Serialize)]
+pub struct JsonEntry<'a> {
+    #[serde(flatten)]
+    task: JsonTask<'a>,
+    project: JsonLocation<'a>,
+}
+
+#[derive(Serialize)]
+struct JsonLocation<'a> {
+    path: &'a str,
+    name: Option<&'a str>,
+    label: String,
+    source: Option<&'a str>,
+}
+
+impl<'a> JsonLocation<'a> {
+    fn new(project: &'a Project) -> Self {
+        Self {
+            path: &project.path,
+            name: project.name.as_deref(),
+            label: project.label(),
+            source: project.source.as_deref(),
+        }
+    }
+}
+
+impl<'a> JsonEntry<'a> {
+    pub fn new(number: usize, task: &'a Task, project: &'a Project) -> Self {
+        Self { task: JsonTask::new(number, task), project: JsonLocation::new(project) }
+    }
+
+    pub fn from_agenda(entry: &'a AgendaEntry) -> Self {
+        Self::located(&entry.task, entry.number, &entry.project_path, entry.project_name.as_deref(), entry.source.as_deref())
+    }
+
+    pub fn from_search(result: &'a SearchResult) -> Self {
+        Self::located(&result.task, result.number, &result.project_path, result.project_name.as_deref(), result.source.as_deref())
+    }
+
+    fn located(task: &'a Task, number: usize, path: &'a str, name: Option<&'a str>, source: Option<&'a str>) -> Self {
+        Self {
+            task: JsonTask::new(number, task),
+            project: JsonLocation { path, name, label: project_label(name, path), source },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonTemplate<'a> {
+    name: &'a str,
+    tasks: Vec<JsonTemplateTask<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonTemplateTask<'a> {
+    name: &'a str,
+    /// Seconds from the anchor the template is applied at.
+    due_offset: Option<i64>,
+}
+
+impl<'a> JsonTemplate<'a> {
+    pub fn new(template: &'a Template) -> Self {
+        Self {
+            name: &template.name,
+            tasks: template.tasks.iter().map(|t| JsonTemplateTask { name: &t.name, due_offset: t.due_offset }).collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonWorkspace<'a> {
+    name: &'a str,
+    projects: Vec<JsonLocation<'a>>,
+}
+
+impl<'a> JsonWorkspace<'a> {
+    pub fn new(workspace: &'a Workspace) -> Self {
+        Self { name: &workspace.name, projects: workspace.projects.iter().map(JsonLocation::new).collect() }
+    }
+}
+
+#[derive(Serialize)]
+pub struct JsonProfile<'a> {
+    pub name: &'a str,
+    pub active: bool,
+}
+
+/// An effective configuration value, as printed by `task config get` and `task config list`.
+#[derive(Serialize)]
+pub struct JsonConfigEntry<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    /// Where the value came from, as shown by `task config list --show-origin`.
+    pub origin: String,
+}
+
+fn iso_timestamp(unix_time: i64) -> Option<String> {
+    Local
+        .timestamp_opt(unix_time, 0)
+        .single()
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::types::TemplateTask;
+
+    fn task(id: u32, name: &str, expiration: Option<i64>) -> Task {
+        Task { id, project_id: 3, name: name.to_string(), finished: false, expiration }
+    }
+
+    fn project() -> Project {
+        Project {
+            id: 3,
+            path: "/home/user/app".to_string(),
+            name: Some("app".to_string()),
+            aliases: vec!["a".to_string()],
+            settings: ProjectSettings { default_due: Some("1w".to_string()), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn project_document_has_the_v1_shape() {
+        let project = project();
+        let tasks = [task(7, "Write docs", None)];
+        let document = render("project", JsonProject::new(&project, vec![JsonTask::new(1, &tasks[0])])).unwrap();
+
+        assert!(document.trim_start_matches(['{', '\n', ' ']).starts_with("\"version\": 1"));
+        assert_eq!(
+            serde_json::from_str::<Value>(&document).unwrap(),
+            json!({
+                "version": 1,
+                "project": {
+                    "id": 3,
+                    "path": "/home/user/app",
+                    "name": "app",
+                    "label": project.label(),
+                    "aliases": ["a"],
+                    "archived": false,
+                    "missing": project.is_missing(),
+                    "parent_id": null,
+                    "source": null,
+                    "settings": { "description": null, "default_due": "1w", "color": null, "hide_finished": null },
+                    "tasks": [{ "number": 1, "id": 7, "name": "Write docs", "finished": false, "due": null }],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn entry_document_has_the_v1_shape() {
+        let project = project();
+        let due = Local.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap();
+        let task = task(7, "Write docs", Some(due.timestamp()));
+        let document = render("tasks", [JsonEntry::new(2, &task, &project)]).unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&document).unwrap(),
+            json!({
+                "version": 1,
+                "tasks": [{
+                    "number": 2,
+                    "id": 7,
+                    "name": "Write docs",
+                    "finished": false,
+                    "due": due.to_rfc3339_opts(SecondsFormat::Secs, false),
+                    "project": { "path": "/home/user/app", "name": "app", "label": project.label(), "source": null },
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn template_and_workspace_documents_have_the_v1_shape() {
+        let template = Template {
+            id: 1,
+            name: "release".to_string(),
+            tasks: vec![TemplateTask { id: 1, template_id: 1, name: "Tag".to_string(), due_offset: Some(3600) }],
+        };
+        let document = render("templates", [JsonTemplate::new(&template)]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&document).unwrap(),
+            json!({ "version": 1, "templates": [{ "name": "release", "tasks": [{ "name": "Tag", "due_offset": 3600 }] }] })
+        );
+
+        let workspace = Workspace { id: 1, name: "work".to_string(), projects: vec![project()] };
+        let document = render("workspaces", [JsonWorkspace::new(&workspace)]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&document).unwrap(),
+            json!({
+                "version": 1,
+                "workspaces": [{
+                    "name": "work",
+                    "projects": [{ "path": "/home/user/app", "name": "app", "label": project().label(), "source": null }],
+                }],
+            })
+        );
+    }
+}
@@ -1,2 +1,9 @@
 mod task_io;
-pub use task_io::TaskIO;
+pub use task_io::{ListOptions, TaskIO};
+
+mod json;
+
+mod table;
+
+mod theme;
+pub use theme::Theme;
@@ -0,0 +1,325 @@
+use std::io;
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use super::theme::Style;
+
+/// Flexible columns are never truncated below this width.
+const MIN_FLEXIBLE_WIDTH: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Right,
+}
+
+pub struct Column {
+    align: Align,
+    flexible: bool,
+    gap: usize,
+}
+
+impl Column {
+    pub fn left() -> Self {
+        Self { align: Align::Left, flexible: false, gap: 1 }
+    }
+
+    pub fn right() -> Self {
+        Self { align: Align::Right, flexible: false, gap: 1 }
+    }
+
+    /// Lets the column be truncated when the table is wider than the terminal.
+    pub fn flexible(mut self) -> Self {
+        self.flexible = true;
+        self
+    }
+
+    /// Number of spaces separating the column from the previous one.
+    pub fn gap(mut self, gap: usize) -> Self {
+        self.gap = gap;
+        self
+    }
+}
+
+/// Text of a table cell, made of differently styled parts.
+#[derive(Clone, Default)]
+pub struct Cell {
+    spans: Vec<(String, Style)>,
+}
+
+impl Cell {
+    pub fn new(text: impl Into<String>, style: &Style) -> Self {
+        Self::default().push(text, style)
+    }
+
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self::new(text, &Style::default())
+    }
+
+    pub fn push(mut self, text: impl Into<String>, style: &Style) -> Self {
+        self.spans.push((text.into(), style.clone()));
+        self
+    }
+
+    /// Number of terminal columns the text takes up.
+    fn width(&self) -> usize {
+        self.spans.iter().map(|(text, _)| text.width()).sum()
+    }
+
+    /// Cuts the text to `width` columns, ending it with an ellipsis.
+    fn truncate(&self, width: usize) -> Cell {
+        if self.width() <= width {
+            return self.clone();
+        }
+        if width == 0 {
+            return Cell::default();
+        }
+
+        let budget = width - 1;
+        let mut used = 0;
+        let mut truncated = Cell::default();
+        for (text, style) in &self.spans {
+            let mut kept = String::new();
+            for c in text.chars() {
+                let char_width = c.width().unwrap_or(0);
+                if used + char_width > budget {
+                    break;
+                }
+                used += char_width;
+                kept.push(c);
+            }
+
+            let complete = kept.len() == text.len();
+            truncated.spans.push((kept, style.clone()));
+            if !complete {
+                break;
+            }
+        }
+
+        let style = truncated.spans.last().map(|(_, style)| style.clone()).unwrap_or_default();
+        truncated.push("…", &style)
+    }
+
+    fn render(&self) -> String {
+        self.spans
+            .iter()
+            .filter(|(text, _)| !text.is_empty())
+            .map(|(text, style)| style.apply(text).to_string())
+            .collect()
+    }
+}
+
+/// Aligns rows of cells into columns measured by display width, truncating
+/// flexible columns to fit the terminal.
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<Cell>>,
+    indent: usize,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self { columns, rows: Vec::new(), indent: 0 }
+    }
+
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    pub fn print(&self) {
+        for line in self.render(terminal_width()) {
+            println!("{}", line);
+        }
+    }
+
+    fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let mut widths = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, _)| self.rows.iter().filter_map(|row| row.get(index)).map(Cell::width).max().unwrap_or(0))
+            .collect::<Vec<_>>();
+
+        if let Some(max_width) = max_width {
+            self.fit(&mut widths, max_width);
+        }
+
+        self.rows
+            .iter()
+            .map(|row| {
+                // columns without any text are left out along with their gap
+                let last = row.iter().enumerate().rposition(|(index, cell)| widths[index] > 0 && cell.width() > 0);
+                let mut line = " ".repeat(self.indent);
+                let mut first = true;
+                for (index, column) in self.columns.iter().enumerate() {
+                    if widths[index] == 0 {
+                        continue;
+                    }
+                    if last.is_none_or(|last| index > last) {
+                        break;
+                    }
+                    if !first {
+                        line.push_str(&" ".repeat(column.gap));
+                    }
+                    first = false;
+
+                    let cell = row.get(index).map(|cell| cell.truncate(widths[index])).unwrap_or_default();
+                    let padding = " ".repeat(widths[index] - cell.width());
+                    match column.align {
+                        Align::Right => {
+                            line.push_str(&padding);
+                            line.push_str(&cell.render());
+                        },
+                        Align::Left => {
+                            line.push_str(&cell.render());
+                            if Some(index) != last {
+                                line.push_str(&padding);
+                            }
+                        },
+                    }
+                }
+                line
+            })
+            .collect()
+    }
+
+    /// Shrinks the widest flexible column until the table fits in `max_width`.
+    fn fit(&self, widths: &mut [usize], max_width: usize) {
+        let total = |widths: &[usize]| {
+            let gaps = self
+                .columns
+                .iter()
+                .zip(widths)
+                .filter(|(_, width)| **width > 0)
+                .skip(1)
+                .map(|(column, _)| column.gap)
+                .sum::<usize>();
+            self.indent + gaps + widths.iter().sum::<usize>()
+        };
+
+        let natural = widths.to_vec();
+        let mut excess = total(widths).saturating_sub(max_width);
+        while excess > 0 {
+            let widest = self
+                .columns
+                .iter()
+                .enumerate()
+                .filter(|(index, column)| column.flexible && widths[*index] > MIN_FLEXIBLE_WIDTH.min(natural[*index]))
+                .max_by_key(|(index, _)| widths[*index]);
+            let Some((index, _)) = widest else { break };
+            widths[index] -= 1;
+            excess -= 1;
+        }
+    }
+}
+
+/// Width of the terminal standard output is written to, or of `COLUMNS` when
+/// it is not a terminal. Output is never truncated when neither is known.
+fn terminal_width() -> Option<usize> {
+    match terminal_size::terminal_size_of(io::stdout()) {
+        Some((terminal_size::Width(width), _)) => Some(width as usize),
+        None => std::env::var("COLUMNS").ok().and_then(|columns| columns.parse().ok()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(table: &Table, max_width: Option<usize>) -> Vec<String> {
+        colored::control::set_override(false);
+        table.render(max_width)
+    }
+
+    fn texts(cell: &Cell) -> Vec<&str> {
+        cell.spans.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    #[test]
+    fn aligns_by_display_width() {
+        let mut table = Table::new(vec![Column::left(), Column::right()]);
+        table.row(vec![Cell::plain("日本語"), Cell::plain("1")]);
+        table.row(vec![Cell::plain("🍣 sushi"), Cell::plain("22")]);
+        table.row(vec![Cell::plain("café"), Cell::plain("333")]);
+
+        assert_eq!(render(&table, None), vec!["日本語     1", "🍣 sushi  22", "café     333"]);
+    }
+
+    #[test]
+    fn truncates_flexible_columns_to_fit() {
+        let mut table = Table::new(vec![Column::right(), Column::left().flexible(), Column::right()]).indent(1);
+        table.row(vec![Cell::plain("1."), Cell::plain("A task with a rather long name"), Cell::plain("2d")]);
+        table.row(vec![Cell::plain("2."), Cell::plain("Short"), Cell::plain("DONE")]);
+
+        assert_eq!(render(&table, Some(20)), vec![" 1. A task wit…   2d", " 2. Short       DONE"]);
+        assert_eq!(render(&table, None)[0], " 1. A task with a rather long name   2d");
+    }
+
+    #[test]
+    fn truncates_styled_spans_and_keeps_the_style_of_the_cut_span() {
+        let highlight = Style::parse("yellow bold").unwrap();
+        let cell = Cell::plain("release ").push("candidate", &highlight).push(" notes", &Style::default());
+
+        let truncated = cell.truncate(12);
+        assert_eq!(texts(&truncated), vec!["release ", "can", "…"]);
+        assert!(truncated.spans[1].1 == highlight && truncated.spans[2].1 == highlight);
+        assert_eq!(truncated.width(), 12);
+    }
+
+    #[test]
+    fn truncates_wide_characters_without_splitting_them() {
+        let cell = Cell::plain("日本語のタスク");
+
+        let truncated = cell.truncate(6);
+        assert_eq!(texts(&truncated), vec!["日本", "…"]);
+        // the second half of the third character is left empty
+        assert_eq!(truncated.width(), 5);
+    }
+
+    #[test]
+    fn never_truncates_below_the_minimum_width() {
+        let mut table = Table::new(vec![Column::left().flexible(), Column::left()]);
+        table.row(vec![Cell::plain("abcdefghijklmnop"), Cell::plain("x")]);
+
+        let line = &render(&table, Some(5))[0];
+        assert_eq!(line, "abcdefghi… x");
+        assert_eq!(line.width(), MIN_FLEXIBLE_WIDTH + 2);
+
+        // shorter text is left as is
+        let mut table = Table::new(vec![Column::left().flexible()]);
+        table.row(vec![Cell::plain("abcdef")]);
+        assert_eq!(render(&table, Some(3)), vec!["abcdef"]);
+    }
+
+    #[test]
+    fn shrinks_the_widest_flexible_column_first() {
+        let mut table = Table::new(vec![Column::left().flexible(), Column::left().flexible()]);
+        table.row(vec![Cell::plain("a".repeat(12)), Cell::plain("b".repeat(20))]);
+
+        assert_eq!(render(&table, Some(29)), vec![format!("{} {}…", "a".repeat(12), "b".repeat(15))]);
+    }
+
+    #[test]
+    fn leaves_out_trailing_padding_and_empty_cells() {
+        let mut table = Table::new(vec![Column::left(), Column::left(), Column::right()]);
+        table.row(vec![Cell::plain("a"), Cell::plain("long"), Cell::default()]);
+        table.row(vec![Cell::plain("b"), Cell::default(), Cell::default()]);
+        table.row(vec![Cell::plain("c"), Cell::default(), Cell::plain("1")]);
+
+        assert_eq!(render(&table, None), vec!["a long", "b", "c      1"]);
+    }
+
+    #[test]
+    fn leaves_out_empty_columns_with_their_gap() {
+        let mut table = Table::new(vec![Column::left(), Column::left().gap(3), Column::left()]);
+        table.row(vec![Cell::plain("a"), Cell::default(), Cell::plain("x")]);
+        table.row(vec![Cell::plain("bb"), Cell::plain(""), Cell::plain("y")]);
+
+        assert_eq!(render(&table, None), vec!["a  x", "bb y"]);
+    }
+}
@@ -1,133 +1,860 @@
-use crate::types::{Project, Task};
+use crate::config::{Origin, Report, ReportColumn, TaskSort};
+use super::Theme;
+use super::json::{self, JsonConfigEntry, JsonEntry, JsonProfile, JsonProject, JsonTask, JsonTemplate, JsonWorkspace};
+use super::table::{Cell, Column, Table};
+use super::theme::Style;
+use crate::storage::{MATCH_END, MATCH_START};
+use crate::types::{AgendaEntry, Project, SearchResult, Task, Template, Workspace};
 use anyhow::Result;
 use colored::*;
-use chrono::Utc;
-use crate::utils::unix_to_relative;
-use std::io::{self, Write};
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Utc};
+use crate::utils::{home_relative, project_label, unix_to_relative};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 
-pub struct TaskIO;
+pub struct TaskIO {
+    theme: Theme,
+    /// Print read commands as JSON, see `json::VERSION`.
+    json: bool,
+    failed: std::cell::Cell<bool>,
+    /// Set when a project lookup already explained why it found nothing.
+    project_explained: std::cell::Cell<bool>,
+}
+
+/// How the tasks of a project are listed.
+pub struct ListOptions {
+    pub hide_finished: bool,
+    pub sort: TaskSort,
+}
 
 impl TaskIO {
-    pub fn new() -> Self {
-        Self
+    pub fn new(theme: Theme, json: bool) -> Self {
+        Self {
+            theme,
+            json,
+            failed: std::cell::Cell::new(false),
+            project_explained: std::cell::Cell::new(false),
+        }
     }
 }
 
 impl TaskIO {
     pub fn new_project(&self) {
-        println!("Created new project");
+        self.inform("Created new project");
+    }
+
+    pub fn project_marked(&self) {
+        self.inform("Wrote marker file for the existing project");
+    }
+
+    pub fn project_relinked(&self) {
+        self.inform("Linked project to current directory");
+    }
+
+    pub fn new_git_project(&self, path: &str) {
+        self.inform(&format!("Created new project for git repository '{}'", path));
     }
 
-    pub fn list_projects(&self, projects: &[Project]) {
-        let project_path_width = projects.iter().map(|p| p.path.len()).max().unwrap_or(1);
+    pub fn list_projects(&self, projects: &[Project]) -> Result<()> {
+        if self.json {
+            let projects = projects
+                .iter()
+                .map(|p| JsonProject::new(p, p.tasks.iter().enumerate().map(|(i, t)| JsonTask::new(i + 1, t)).collect()))
+                .collect::<Vec<_>>();
+            return json::print("projects", projects);
+        }
+
+        let mut table = Table::new(vec![
+            Column::left().flexible(),
+            Column::left().flexible(),
+            Column::left(),
+            Column::left(),
+        ]);
+        for (label, project) in project_tree(projects) {
+            // named projects show their path next to the name
+            let path = if project.name.is_some() { home_relative(&project.path) } else { String::new() };
+            let count = format!("({} task{})", project.tasks.len(), if project.tasks.len() == 1 { "" } else { "s" });
+
+            let mut notes = Vec::new();
+            if !project.aliases.is_empty() {
+                notes.push((format!("aka {}", project.aliases.join(", ")), &self.theme.detail));
+            }
+            if project.archived {
+                notes.push(("archived".to_string(), &self.theme.archived));
+            }
+            if project.is_missing() {
+                notes.push(("directory missing".to_string(), &self.theme.missing));
+            }
+            let mut note_cell = Cell::default();
+            for (index, (note, style)) in notes.into_iter().enumerate() {
+                if index > 0 {
+                    note_cell = note_cell.push(" ", &Style::default());
+                }
+                note_cell = note_cell.push(note, style);
+            }
+
+            table.row(vec![Cell::plain(label), Cell::new(path, &self.theme.detail), Cell::plain(count), note_cell]);
+        }
 
         println!("Projects:\n---------");
+        table.print();
+
+        Ok(())
+    }
+
+    /// Whether the user can be asked to choose, which is never the case with
+    /// `--json` as the output is meant for another program.
+    pub fn is_interactive(&self) -> bool {
+        !self.json && io::stdin().is_terminal() && io::stdout().is_terminal()
+    }
+
+    pub fn choose_project(&self, name: &str, projects: &[Project]) -> Result<Option<u32>> {
+        println!("'{}' matches several projects:", name);
+        for (index, project) in projects.iter().enumerate() {
+            println!("{: >3}. {}", index + 1, home_relative(&project.path));
+        }
+        print!("Which one do you mean? (1-{}): ", projects.len());
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .and_then(|number| projects.get(number.wrapping_sub(1)))
+            .map(|project| project.id))
+    }
+
+    pub fn project_name_taken(&self) {
+        self.fail("Another project already uses that name");
+    }
+
+    pub fn alias_not_found(&self) {
+        self.fail("Alias not found");
+    }
+
+    pub fn alias_already_exists(&self) {
+        self.fail("The project already has that alias");
+    }
+
+    pub fn project_already_exists(&self) {
+        self.fail("The current directory already is a project");
+    }
+
+    pub fn directory_taken(&self) {
+        self.fail("Another project already uses that directory");
+    }
+
+    pub fn not_a_directory(&self) {
+        self.fail("The path is not a directory");
+    }
+
+    pub fn no_missing_projects(&self) {
+        self.inform("No projects with missing directories");
+    }
+
+    pub fn confirm_prune_projects(&self, projects: &[Project], reassign: bool) -> Result<bool> {
+        self.inform("Projects with missing directories:");
         for project in projects {
-            println!(
-                "{: <project_path_width$} ({} task{})",
-                project.path,
+            self.inform(&format!(
+                "  {} ({} task{})",
+                home_relative(&project.path),
                 project.tasks.len(),
-                if project.tasks.len() == 1 { "" } else { "s" }
-            );
+                if project.tasks.len() == 1 { "" } else { "s" },
+            ));
+        }
+        if reassign {
+            self.confirm("Are you sure you want to move their tasks and delete these projects?")
+        } else {
+            self.confirm("Are you sure you want to delete these projects and their tasks?")
+        }
+    }
+
+    pub fn print_project_info(&self, project: &Project) -> Result<()> {
+        if self.json {
+            let tasks = project.tasks.iter().enumerate().map(|(i, t)| JsonTask::new(i + 1, t)).collect();
+            return json::print("project", JsonProject::new(project, tasks));
+        }
+
+        println!("{}", colorize(&project.label(), project.settings.color.as_deref()).bold());
+        println!("  path: {}", home_relative(&project.path));
+        if !project.aliases.is_empty() {
+            println!("  aliases: {}", project.aliases.join(", "));
+        }
+        if project.archived {
+            println!("  archived");
+        }
+
+        let entries = project.settings.entries();
+        if entries.is_empty() {
+            println!("  no settings");
+        }
+        for (key, value) in entries {
+            println!("  {}: {}", key, value);
+        }
+
+        Ok(())
+    }
+
+    pub fn list_templates(&self, templates: &[Template]) -> Result<()> {
+        if self.json {
+            return json::print("templates", templates.iter().map(JsonTemplate::new).collect::<Vec<_>>());
+        }
+
+        let mut table = Table::new(vec![Column::left().flexible(), Column::left()]);
+        for template in templates {
+            table.row(vec![
+                Cell::plain(&template.name),
+                Cell::plain(format!("({} task{})", template.tasks.len(), if template.tasks.len() == 1 { "" } else { "s" })),
+            ]);
+        }
+
+        println!("Templates:\n----------");
+        table.print();
+
+        Ok(())
+    }
+
+    pub fn list_workspaces(&self, workspaces: &[Workspace]) -> Result<()> {
+        if self.json {
+            return json::print("workspaces", workspaces.iter().map(JsonWorkspace::new).collect::<Vec<_>>());
         }
+
+        let mut table = Table::new(vec![Column::left().flexible(), Column::left().flexible()]);
+        for workspace in workspaces {
+            let labels = workspace.projects.iter().map(|p| p.label()).collect::<Vec<_>>();
+            table.row(vec![Cell::plain(&workspace.name), Cell::new(labels.join(", "), &self.theme.detail)]);
+        }
+
+        println!("Workspaces:\n-----------");
+        table.print();
+
+        Ok(())
+    }
+
+    pub fn list_profiles(&self, profiles: &[String], active: &str) -> Result<()> {
+        if self.json {
+            let profiles = profiles.iter().map(|p| JsonProfile { name: p, active: p == active }).collect::<Vec<_>>();
+            return json::print("profiles", profiles);
+        }
+
+        println!("Profiles:\n---------");
+        for profile in profiles {
+            if profile == active {
+                println!("{} {}", profile.bold(), self.theme.detail.apply("(active)"));
+            } else {
+                println!("{}", profile);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn profile_created(&self, name: &str) {
+        self.inform(&format!("Created profile '{}'", name));
+    }
+
+    pub fn template_saved(&self, name: &str) {
+        self.inform(&format!("Saved template '{}'", name));
     }
 
-    pub fn print_tasks(&self, project: &Project, hide_finished: bool) -> Result<()> {
+    pub fn print_tasks(&self, project: &Project, options: &ListOptions) -> Result<()> {
+        if self.json {
+            return json::print("projects", [json_project(project, options)]);
+        }
         if project.tasks.is_empty() {
             println!("No tasks yet. Create one with `task add \"My task\"`");
             return Ok(());
         }
 
-        let id_width = project.tasks.iter().map(|t| t.id.to_string().len()).max().unwrap_or(0);
-        let name_width = project.tasks.iter().map(|t| t.name.len()).max().unwrap_or(0);
-        let last_width = project
-            .tasks
-            .iter()
-            .map(|t| {
-                if t.finished {
-                    4 // evaluates to "DONE" later
-                } else if let Some(exp) = t.expiration {
-                    unix_to_relative(exp).len()
-                } else {
-                    0
-                }
-            })
-        .max()
-        .unwrap_or(0);
+        let color = project.settings.color.as_deref();
+        match project.name {
+            Some(ref name) => print!("Listing tasks in project '{}' ({})", colorize(name, color), home_relative(&project.path)),
+            None => print!("Listing tasks in project '{}'", colorize(&home_relative(&project.path), color)),
+        }
+        match project.source {
+            Some(ref source) => println!(" from database '{}'", source),
+            None => println!(),
+        }
+        self.print_task_rows(&project.tasks, options);
 
-        println!("Listing tasks in project '{}'", project.path);
+        Ok(())
+    }
 
-        for (index, task) in project.tasks.iter().enumerate() {
-            if task.finished && hide_finished {
-                continue;
-            }
+    /// Prints the tasks of a project, followed by those of its sub-projects
+    /// and of the same project in additional databases.
+    pub fn print_project_tasks(
+        &self,
+        project: &Project,
+        options: &ListOptions,
+        sub_projects: &[Project],
+        source_projects: &[(Project, ListOptions)],
+    ) -> Result<()> {
+        let sub_projects = sub_projects.iter().filter(|p| !p.tasks.is_empty());
+        let source_projects = source_projects.iter().filter(|(p, _)| !p.tasks.is_empty());
+        if self.json {
+            let projects = [json_project(project, options)]
+                .into_iter()
+                .chain(sub_projects.map(|p| json_project(p, options)))
+                .chain(source_projects.map(|(p, options)| json_project(p, options)))
+                .collect::<Vec<_>>();
+            return json::print("projects", projects);
+        }
+
+        self.print_tasks(project, options)?;
+        for sub_project in sub_projects {
+            println!();
+            println!("Sub-project '{}'", sub_project.label_within(project));
+            self.print_task_rows(&sub_project.tasks, options);
+        }
+        for (source_project, options) in source_projects {
+            println!();
+            self.print_tasks(source_project, options)?;
+        }
+
+        Ok(())
+    }
 
-            let styled_name = if task.finished {
-                task.name.bright_black().strikethrough()
+    fn print_task_rows(&self, tasks: &[Task], options: &ListOptions) {
+        let mut table = Table::new(vec![Column::right(), Column::left().flexible(), Column::right()]).indent(1);
+        for (number, task) in visible_tasks(tasks, options) {
+            let name = if task.finished {
+                Cell::new(&task.name, &self.theme.finished)
             } else {
-                task.name.white().bold()
+                Cell::new(&task.name, &self.theme.task)
             };
 
-            let raw_last = if task.finished {
-                "DONE".to_string()
+            let last = if task.finished {
+                Cell::new("DONE", &self.theme.done)
             } else if let Some(exp) = task.expiration {
-                unix_to_relative(exp)
+                Cell::new(unix_to_relative(exp), self.theme.due_style(exp - Utc::now().timestamp()))
             } else {
-                "".to_string()
+                Cell::default()
             };
 
-            let styled_last = if task.finished {
-                raw_last.green()
-            } else if let Some(exp) = task.expiration {
-                if exp - Utc::now().timestamp() <= 0 {
-                    raw_last.red()
-                } else {
-                    raw_last.bright_black()
+            table.row(vec![Cell::plain(format!("{}.", number)), name, last]);
+        }
+        table.print();
+    }
+
+    pub fn print_all_tasks(&self, projects: &[(Project, ListOptions)]) -> Result<()> {
+        if self.json {
+            let projects = projects.iter().map(|(p, options)| json_project(p, options)).collect::<Vec<_>>();
+            return json::print("projects", projects);
+        }
+
+        for (index, (project, options)) in projects.iter().enumerate() {
+            if index > 0 {
+                println!();
+            }
+            self.print_tasks(project, options)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn print_task_details(&self, project: &Project, number: usize, task: &Task) -> Result<()> {
+        if self.json {
+            return json::print("task", JsonEntry::new(number, task, project));
+        }
+
+        let style = if task.finished { &self.theme.finished } else { &self.theme.task };
+        println!("{}", style.apply(&task.name));
+        println!("  project: {}", colorize(&project.label(), project.settings.color.as_deref()));
+        println!("  number: {}", number);
+        if task.finished {
+            println!("  status: {}", self.theme.done.apply("done"));
+        } else {
+            println!("  status: pending");
+        }
+        if let Some(exp) = task.expiration {
+            let relative = unix_to_relative(exp);
+            let styled = if task.finished {
+                self.theme.detail.apply(&relative)
+            } else {
+                self.theme.due_style(exp - Utc::now().timestamp()).apply(&relative)
+            };
+            println!("  due: {} ({})", absolute_time(exp), styled);
+        }
+
+        Ok(())
+    }
+
+    pub fn print_agenda(&self, entries: &[AgendaEntry]) -> Result<()> {
+        if self.json {
+            return json::print("tasks", entries.iter().map(JsonEntry::from_agenda).collect::<Vec<_>>());
+        }
+        if entries.is_empty() {
+            println!("Nothing due");
+            return Ok(());
+        }
+
+        let location = |e: &AgendaEntry| format!(
+            "{}{}:{}",
+            source_prefix(e.source.as_deref()),
+            project_label(e.project_name.as_deref(), &e.project_path),
+            e.number,
+        );
+        let columns = || vec![Column::left(), Column::left().flexible().gap(2), Column::left()];
+        let mut table = Table::new(columns()).indent(2);
+
+        let mut current_day = None;
+        for entry in entries {
+            // entries only contain tasks with a due date
+            let Some(exp) = entry.task.expiration else { continue };
+            let due = Local.timestamp_opt(exp, 0).single().unwrap_or_else(Local::now);
+
+            if current_day != Some(due.date_naive()) {
+                if current_day.is_some() {
+                    table.print();
+                    table = Table::new(columns()).indent(2);
+                    println!();
                 }
+                println!("{}", due.format("%A %-d %B %Y").to_string().bold());
+                current_day = Some(due.date_naive());
+            }
+
+            table.row(vec![
+                Cell::new(location(entry), &self.project_style_or_detail(entry.project_color.as_deref())),
+                Cell::new(&entry.task.name, &self.theme.task),
+                Cell::new(unix_to_relative(exp), self.theme.due_style(exp - Utc::now().timestamp())),
+            ]);
+        }
+        table.print();
+
+        Ok(())
+    }
+
+    pub fn print_report(&self, report: &Report, entries: &[AgendaEntry]) -> Result<()> {
+        if self.json {
+            return json::print("tasks", entries.iter().map(JsonEntry::from_agenda).collect::<Vec<_>>());
+        }
+        if entries.is_empty() {
+            println!("No matching tasks");
+            return Ok(());
+        }
+
+        // laid out like the rows of `print_tasks`
+        let columns = report
+            .columns
+            .iter()
+            .map(|column| match column {
+                ReportColumn::Number | ReportColumn::DueRelative => Column::right(),
+                ReportColumn::Name | ReportColumn::Project => Column::left().flexible(),
+                ReportColumn::DueAbsolute | ReportColumn::Status => Column::left(),
+            })
+            .collect();
+        let mut table = Table::new(columns).indent(1);
+        for entry in entries {
+            let task = &entry.task;
+            let row = report
+                .columns
+                .iter()
+                .map(|column| match (column, task.expiration) {
+                    (ReportColumn::Number, _) => Cell::plain(format!("{}.", entry.number)),
+                    (ReportColumn::Name, _) if task.finished => Cell::new(&task.name, &self.theme.finished),
+                    (ReportColumn::Name, _) => Cell::new(&task.name, &self.theme.task),
+                    (ReportColumn::DueRelative, Some(exp)) if task.finished => Cell::new(unix_to_relative(exp), &self.theme.detail),
+                    (ReportColumn::DueRelative, Some(exp)) => {
+                        Cell::new(unix_to_relative(exp), self.theme.due_style(exp - Utc::now().timestamp()))
+                    },
+                    (ReportColumn::DueAbsolute, Some(exp)) => Cell::new(absolute_time(exp), &self.theme.detail),
+                    (ReportColumn::DueRelative | ReportColumn::DueAbsolute, None) => Cell::default(),
+                    (ReportColumn::Status, _) if task.finished => Cell::new("done", &self.theme.done),
+                    (ReportColumn::Status, _) => Cell::new("pending", &self.theme.detail),
+                    (ReportColumn::Project, _) => Cell::new(
+                        format!("{}{}", source_prefix(entry.source.as_deref()), project_label(entry.project_name.as_deref(), &entry.project_path)),
+                        &self.project_style_or_detail(entry.project_color.as_deref()),
+                    ),
+                })
+                .collect();
+            table.row(row);
+        }
+
+        println!("Report '{}'", report.name);
+        table.print();
+
+        Ok(())
+    }
+
+    pub fn print_calendar(&self, first: NaiveDate, entries: &[AgendaEntry], list: bool) -> Result<()> {
+        // the days of the month are only useful to look at
+        if self.json {
+            return self.print_agenda(entries);
+        }
+
+        let today = Local::now().date_naive();
+        let now = Utc::now().timestamp();
+
+        // (tasks due, any of them overdue) per day of the month
+        let mut days = [(0, false); 31];
+        for exp in entries.iter().filter_map(|e| e.task.expiration) {
+            if let Some(due) = Local.timestamp_opt(exp, 0).single() {
+                let day = &mut days[due.day0() as usize];
+                day.0 += 1;
+                day.1 |= exp <= now;
+            }
+        }
+
+        println!("{:^49}", first.format("%B %Y").to_string().bold());
+        println!("{}", self.theme.detail.apply(" Mo     Tu     We     Th     Fr     Sa     Su"));
+
+        print!("{:width$}", "", width = first.weekday().num_days_from_monday() as usize * 7);
+        let mut date = first;
+        while date.month() == first.month() {
+            let (count, overdue) = days[date.day0() as usize];
+            let day = format!("{: >3}", date.day());
+            let styled_day = if date == today {
+                day.reversed()
+            } else if overdue {
+                self.theme.overdue.apply(&day).bold()
             } else {
-                raw_last.white()
+                day.normal()
             };
+            let count = if count > 0 { format!("·{: <2}", count) } else { "   ".to_string() };
+            let styled_count = if overdue { self.theme.overdue.apply(&count) } else { self.theme.due_soon.apply(&count) };
+            print!("{}{} ", styled_day, styled_count);
+
+            if date.weekday().num_days_from_monday() == 6 {
+                println!();
+            }
+            // unwrap is safe because the loop ends on the last day of the month
+            date = date.succ_opt().unwrap();
+        }
+        if date.weekday().num_days_from_monday() != 0 {
+            println!();
+        }
+
+        if list && !entries.is_empty() {
+            println!();
+            self.print_agenda(entries)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn print_search_results(&self, results: &[SearchResult]) -> Result<()> {
+        if self.json {
+            return json::print("tasks", results.iter().map(JsonEntry::from_search).collect::<Vec<_>>());
+        }
+        if results.is_empty() {
+            println!("No matching tasks");
+            return Ok(());
+        }
+
+        let location = |r: &SearchResult| format!(
+            "{}{}:{}",
+            source_prefix(r.source.as_deref()),
+            project_label(r.project_name.as_deref(), &r.project_path),
+            r.number,
+        );
+        let mut table = Table::new(vec![Column::left(), Column::left().flexible().gap(2)]);
+        for result in results {
+            // the snippet alternates between plain text and matched terms
+            let mut snippet = Cell::default();
+            for (index, part) in result.snippet.split([MATCH_START, MATCH_END]).enumerate() {
+                if index % 2 == 1 {
+                    snippet = snippet.push(part, &self.theme.matched);
+                } else if result.task.finished {
+                    snippet = snippet.push(part, &self.theme.finished);
+                } else {
+                    snippet = snippet.push(part, &self.theme.task);
+                }
+            }
 
-            print!("{: >id_width$}. ", index + 1, id_width = id_width + 1);
-            print!("{}", styled_name);
-            let name_pad = name_width.saturating_sub(task.name.len());
-            print!("{:name_pad$} ", "", name_pad = name_pad);
-            let last_pad = last_width.saturating_sub(raw_last.len());
-            print!("{:last_pad$}", "", last_pad = last_pad);
-            println!("{}", styled_last);
+            table.row(vec![Cell::new(location(result), &self.project_style_or_detail(result.project_color.as_deref())), snippet]);
         }
+        table.print();
 
         Ok(())
     }
 
     pub fn confirm_delete_project(&self, project: &Project) -> Result<bool> {
-        print!(
-            "Are you sure you want to delete project '{}'? (contains {} task{}) (y/N): ",
-            project.path,
+        self.confirm(&format!(
+            "Are you sure you want to delete project '{}'? (contains {} task{})",
+            project.label(),
             project.tasks.len(),
             if project.tasks.len() == 1 { "" } else { "s"},
-        );
-        io::stdout().flush()?;
+        ))
+    }
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        Ok(input.to_lowercase().contains('y'))
+    pub fn confirm_delete_profile(&self, name: &str) -> Result<bool> {
+        self.confirm(&format!("Are you sure you want to delete profile '{}' and all of its tasks?", name))
     }
 
     pub fn confirm_delete_task(&self, task: &Task) -> Result<bool> {
-        print!("Are you sure you want to delete task '{}'? (y/N): ", task.name);
-        io::stdout().flush()?;
+        self.confirm(&format!("Are you sure you want to delete task '{}'?", task.name))
+    }
+
+    /// Asks a yes or no question, which defaults to no. With `--json` it is
+    /// asked on stderr, see `fail`.
+    fn confirm(&self, question: &str) -> Result<bool> {
+        if self.json {
+            eprint!("{} (y/N): ", question);
+            io::stderr().flush()?;
+        } else {
+            print!("{} (y/N): ", question);
+            io::stdout().flush()?;
+        }
 
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
         Ok(input.to_lowercase().contains('y'))
     }
 
+    /// Printed unless `project_ambiguous` or `project_not_found_suggest`
+    /// already explained the failed lookup.
     pub fn project_not_found(&self) {
-        println!("Project not found");
+        if !self.project_explained.replace(false) {
+            self.fail("Project not found");
+        }
+    }
+
+    pub fn project_not_found_suggest(&self, name: &str, suggestions: &[&str]) {
+        let names = suggestions.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>();
+        self.fail(&format!("Project '{}' not found. Did you mean {}?", name, names.join(" or ")));
+        self.project_explained.set(true);
+    }
+
+    pub fn project_ambiguous(&self, name: &str, candidates: &[Project]) {
+        let paths = candidates.iter().map(|p| format!("  {}", p.path)).collect::<Vec<_>>();
+        self.fail(&format!(
+            "Project '{}' is ambiguous, it matches:\n{}\nUse the full path, or a name or alias set with `task project rename` or `task project alias add`",
+            name,
+            paths.join("\n"),
+        ));
+        self.project_explained.set(true);
+    }
+
+    pub fn cannot_delete_global_project(&self) {
+        self.fail("The global project cannot be deleted");
+    }
+
+    pub fn cannot_archive_global_project(&self) {
+        self.fail("The global project cannot be archived");
+    }
+
+    pub fn project_archived(&self) {
+        self.fail("Project is archived, unarchive it with `task project unarchive` to add tasks");
+    }
+
+    pub fn cannot_relink_global_project(&self) {
+        self.fail("The global project cannot be relinked");
     }
 
     pub fn task_not_found(&self) {
-        println!("Task not found");
+        self.fail("Task not found");
+    }
+
+    pub fn template_not_found(&self) {
+        self.fail("Template not found");
+    }
+
+    /// Prints why a command could not be carried out. With `--json` the
+    /// message goes to stderr and the command exits with an error, so that
+    /// standard output is either a JSON document or empty.
+    fn fail(&self, message: &str) {
+        if self.json {
+            eprintln!("Error: {}", message);
+            self.failed.set(true);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// Prints what a command did. With `--json` it goes to stderr, see `fail`.
+    fn inform(&self, message: &str) {
+        if self.json {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
     }
+
+    /// Whether a message printed with `--json` reported a failure.
+    pub fn failed(&self) -> bool {
+        self.failed.get()
+    }
+
+    /// Style of a project color setting, or the detail style without one.
+    fn project_style_or_detail(&self, color: Option<&str>) -> Style {
+        match color.and_then(|c| c.parse::<Color>().ok()) {
+            Some(color) => Style::from_color(Some(color)),
+            None => self.theme.detail.clone(),
+        }
+    }
+
+    pub fn print_config_value(&self, key: &str, value: &str, origin: &Origin) -> Result<()> {
+        if self.json {
+            return json::print("entry", JsonConfigEntry { key, value, origin: origin.to_string() });
+        }
+
+        println!("{}", value);
+
+        Ok(())
+    }
+
+    pub fn list_config(&self, entries: &[(String, String, Origin)], show_origin: bool) -> Result<()> {
+        if self.json {
+            let entries = entries
+                .iter()
+                .map(|(key, value, origin)| JsonConfigEntry { key, value, origin: origin.to_string() })
+                .collect::<Vec<_>>();
+            return json::print("entries", entries);
+        }
+
+        let mut table = Table::new(vec![Column::left(), Column::left(), Column::left().flexible(), Column::left().gap(2)]);
+        for (key, value, origin) in entries {
+            let mut row = vec![Cell::plain(key), Cell::plain("="), Cell::plain(value)];
+            if show_origin {
+                row.push(Cell::new(origin.to_string(), &self.theme.detail));
+            }
+            table.row(row);
+        }
+        table.print();
+
+        Ok(())
+    }
+
+    pub fn report_not_found(&self, reports: &[Report]) {
+        if reports.is_empty() {
+            self.fail("Report not found, define reports as `[reports.<name>]` in the configuration file");
+        } else {
+            let names = reports.iter().map(|r| r.name.as_str()).collect::<Vec<_>>();
+            self.fail(&format!("Report not found, configured reports: {}", names.join(", ")));
+        }
+    }
+
+    pub fn config_key_not_found(&self) {
+        self.fail("Unknown configuration key, see `task config list`");
+    }
+
+    pub fn profile_not_found(&self) {
+        self.fail("Profile not found");
+    }
+
+    pub fn profile_already_exists(&self) {
+        self.fail("Profile already exists");
+    }
+
+    pub fn cannot_delete_default_profile(&self) {
+        self.fail("The default profile cannot be deleted");
+    }
+
+    pub fn cannot_delete_active_profile(&self) {
+        self.fail("Cannot delete the profile in use, switch to another profile first");
+    }
+
+    pub fn database_unavailable(&self, name: &str, path: &Path) {
+        eprintln!("{} skipping database '{}', cannot open {}", "warning:".yellow().bold(), name, path.display());
+    }
+
+    pub fn database_unreadable(&self, name: &str, error: &anyhow::Error) {
+        eprintln!("{} skipping database '{}', cannot read it: {}", "warning:".yellow().bold(), name, error);
+    }
+
+    pub fn setting_ignored(&self, database: Option<&str>, project_id: u32, error: &str) {
+        match database {
+            Some(name) => eprintln!(
+                "{} ignoring setting of project {} in database '{}': {}",
+                "warning:".yellow().bold(),
+                project_id,
+                name,
+                error,
+            ),
+            None => eprintln!("{} ignoring setting of project {}: {}", "warning:".yellow().bold(), project_id, error),
+        }
+    }
+
+    pub fn workspace_not_found(&self) {
+        self.fail("Workspace not found");
+    }
+
+    pub fn workspace_already_exists(&self) {
+        self.fail("Workspace already exists");
+    }
+
+    pub fn project_not_in_workspace(&self) {
+        self.fail("Project is not part of the workspace");
+    }
+
+    pub fn invalid_month(&self) {
+        self.fail("Invalid month");
+    }
+
+    pub fn invalid_time(&self) {
+        self.fail("Invalid time");
+    }
+}
+
+/// Orders projects as a tree of nested directories, pairing each project with
+/// its label indented by its depth.
+fn project_tree(projects: &[Project]) -> Vec<(String, &Project)> {
+    let mut tree = Vec::new();
+    let roots = projects
+        .iter()
+        .filter(|p| p.parent_id.is_none_or(|id| projects.iter().all(|o| o.id != id)));
+    for root in roots {
+        tree.push((root.label(), root));
+        add_project_subtree(root, projects, "", &mut tree);
+    }
+
+    tree
+}
+
+fn add_project_subtree<'a>(parent: &Project, projects: &'a [Project], prefix: &str, tree: &mut Vec<(String, &'a Project)>) {
+    let children: Vec<_> = projects.iter().filter(|p| p.parent_id == Some(parent.id)).collect();
+
+    for (index, child) in children.iter().enumerate() {
+        let last = index == children.len() - 1;
+        let branch = if last { "└─ " } else { "├─ " };
+        tree.push((format!("{}{}{}", prefix, branch, child.label_within(parent)), child));
+
+        let indent = if last { "   " } else { "│  " };
+        add_project_subtree(child, projects, &format!("{}{}", prefix, indent), tree);
+    }
+}
+
+/// Tasks listed for a project with their numbers, which follow creation order
+/// regardless of the sort.
+fn visible_tasks<'a>(tasks: &'a [Task], options: &ListOptions) -> Vec<(usize, &'a Task)> {
+    let mut rows = tasks
+        .iter()
+        .enumerate()
+        .map(|(index, task)| (index + 1, task))
+        .filter(|(_, task)| !(task.finished && options.hide_finished))
+        .collect::<Vec<_>>();
+    match options.sort {
+        TaskSort::Created => {},
+        TaskSort::Due => rows.sort_by_key(|(_, t)| (t.expiration.is_none(), t.expiration)),
+        TaskSort::Name => rows.sort_by_key(|(_, t)| t.name.to_lowercase()),
+    }
+    rows
+}
+
+fn json_project<'a>(project: &'a Project, options: &ListOptions) -> JsonProject<'a> {
+    let tasks = visible_tasks(&project.tasks, options)
+        .into_iter()
+        .map(|(number, task)| JsonTask::new(number, task))
+        .collect();
+    JsonProject::new(project, tasks)
+}
+
+fn absolute_time(unix_time: i64) -> String {
+    let time = Local.timestamp_opt(unix_time, 0).single().unwrap_or_else(Local::now);
+    time.format("%Y-%m-%d %H:%M").to_string()
+}
+
+/// Applies a project color setting, leaving the text unstyled without one.
+fn colorize(text: &str, color: Option<&str>) -> ColoredString {
+    match color.and_then(|c| c.parse::<Color>().ok()) {
+        Some(color) => text.color(color),
+        None => text.normal(),
+    }
+}
+
+/// Labels tasks from additional databases with the database name.
+fn source_prefix(source: Option<&str>) -> String {
+    source.map(|source| format!("[{}] ", source)).unwrap_or_default()
 }
@@ -0,0 +1,179 @@
+use anyhow::{Result, bail};
+use colored::{Color, ColoredString, Colorize};
+
+use crate::utils::parse_duration;
+
+const MODIFIERS: [&str; 6] = ["bold", "dimmed", "italic", "underline", "strikethrough", "reversed"];
+
+/// A foreground color with text attributes, written as e.g. `bright black strikethrough`.
+#[derive(Clone, Default, PartialEq)]
+pub struct Style {
+    color: Option<Color>,
+    modifiers: Vec<&'static str>,
+    spec: String,
+}
+
+impl Style {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut modifiers = Vec::new();
+        let mut color_words = Vec::new();
+        for word in spec.split_whitespace() {
+            match MODIFIERS.iter().find(|modifier| word.eq_ignore_ascii_case(modifier)) {
+                Some(modifier) => modifiers.push(*modifier),
+                None if word.eq_ignore_ascii_case("none") => {},
+                None => color_words.push(word),
+            }
+        }
+
+        let color = match color_words.join(" ") {
+            words if words.is_empty() => None,
+            words => match words.parse::<Color>() {
+                Ok(color) => Some(color),
+                Err(_) => bail!(
+                    "Invalid style '{}', expected a color such as `red`, `bright black` or `#ff8800` and any of: {}",
+                    spec,
+                    MODIFIERS.join(", "),
+                ),
+            },
+        };
+
+        Ok(Self { color, modifiers, spec: spec.to_string() })
+    }
+
+    pub fn from_color(color: Option<Color>) -> Self {
+        Self { color, ..Self::default() }
+    }
+
+    pub fn apply(&self, text: &str) -> ColoredString {
+        let mut styled = match self.color {
+            Some(color) => text.color(color),
+            None => text.normal(),
+        };
+        for modifier in &self.modifiers {
+            styled = match *modifier {
+                "bold" => styled.bold(),
+                "dimmed" => styled.dimmed(),
+                "italic" => styled.italic(),
+                "underline" => styled.underline(),
+                "strikethrough" => styled.strikethrough(),
+                _ => styled.reversed(),
+            };
+        }
+        styled
+    }
+}
+
+/// Styles of task and project listings, configured under `[theme]`.
+#[derive(Clone)]
+pub struct Theme {
+    /// Name of an unfinished task.
+    pub task: Style,
+    /// Name of a finished task.
+    pub finished: Style,
+    /// The `DONE` marker of a finished task.
+    pub done: Style,
+    pub due: Style,
+    pub due_soon: Style,
+    pub overdue: Style,
+    /// Secondary details such as project paths and aliases.
+    pub detail: Style,
+    pub archived: Style,
+    pub missing: Style,
+    /// Matched terms in search results.
+    pub matched: Style,
+    /// Tasks due within this many seconds use the `due_soon` style.
+    pub due_soon_within: i64,
+    due_soon_within_spec: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = |spec| Style::parse(spec).unwrap_or_default();
+        Self {
+            task: style("white bold"),
+            finished: style("bright black strikethrough"),
+            done: style("green"),
+            due: style("bright black"),
+            due_soon: style("yellow"),
+            overdue: style("red"),
+            detail: style("bright black"),
+            archived: style("yellow"),
+            missing: style("red"),
+            matched: style("yellow bold"),
+            due_soon_within: 24 * 3600,
+            due_soon_within_spec: "1d".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    pub const KEYS: [&str; 11] = [
+        "task",
+        "finished",
+        "done",
+        "due",
+        "due_soon",
+        "overdue",
+        "detail",
+        "archived",
+        "missing",
+        "match",
+        "due_soon_within",
+    ];
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        if key == "due_soon_within" {
+            let Some(duration) = parse_duration(value) else {
+                bail!("Invalid duration '{}' for due_soon_within, expected e.g. `1d` or `12h`", value);
+            };
+            self.due_soon_within = duration.num_seconds();
+            self.due_soon_within_spec = value.to_string();
+            return Ok(());
+        }
+
+        let style = Style::parse(value)?;
+        match key {
+            "task" => self.task = style,
+            "finished" => self.finished = style,
+            "done" => self.done = style,
+            "due" => self.due = style,
+            "due_soon" => self.due_soon = style,
+            "overdue" => self.overdue = style,
+            "detail" => self.detail = style,
+            "archived" => self.archived = style,
+            "missing" => self.missing = style,
+            "match" => self.matched = style,
+            _ => bail!("Unknown theme key `{}`, expected one of: {}", key, Self::KEYS.join(", ")),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let style = match key {
+            "task" => &self.task,
+            "finished" => &self.finished,
+            "done" => &self.done,
+            "due" => &self.due,
+            "due_soon" => &self.due_soon,
+            "overdue" => &self.overdue,
+            "detail" => &self.detail,
+            "archived" => &self.archived,
+            "missing" => &self.missing,
+            "match" => &self.matched,
+            "due_soon_within" => return Some(self.due_soon_within_spec.clone()),
+            _ => return None,
+        };
+        Some(style.spec.clone())
+    }
+
+    /// Style of a due date `seconds_left` from now.
+    pub fn due_style(&self, seconds_left: i64) -> &Style {
+        if seconds_left <= 0 {
+            &self.overdue
+        } else if seconds_left <= self.due_soon_within {
+            &self.due_soon
+        } else {
+            &self.due
+        }
+    }
+}
@@ -1,5 +1,6 @@
 mod app;
 mod cli;
+mod config;
 mod io;
 mod storage;
 mod types;
@@ -7,10 +8,69 @@ mod utils;
 
 use anyhow::Result;
 use clap::Parser;
+use std::io::IsTerminal;
 
 fn main() -> Result<()> {
     let cli = cli::Cli::parse();
-    let mut io = io::TaskIO::new();
-    let storage = storage::TaskStorage::new()?;
-    app::run(&storage, &mut io, cli)
+    let mut config = config::Config::load()?;
+    if let Some(profile) = &cli.profile {
+        config.override_profile(profile)?;
+    }
+    if let Some(color) = &cli.color {
+        config.override_color(color)?;
+    }
+    // NO_COLOR only changes the automatic choice, see https://no-color.org
+    colored::control::set_override(match config.color {
+        config::ColorMode::Always => true,
+        config::ColorMode::Never => false,
+        config::ColorMode::Auto => {
+            std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal()
+        },
+    });
+    let mut io = io::TaskIO::new(config.theme.clone(), cli.json);
+    // options take precedence over environment variables, so `--profile` over `TASK_DB`
+    let db = match (&cli.db, storage::get_env_storage_path()) {
+        (Some(db), _) => Ok(db.clone()),
+        (None, Some(db)) if cli.profile.is_none() => Ok(db),
+        _ => storage::get_storage_path(config.profile.as_deref()),
+    };
+    let db = match db {
+        Ok(db) => Some(db),
+        // a missing profile can still be created, or deselected in the configuration
+        Err(_) if matches!(cli.command, cli::Commands::Profile { .. } | cli::Commands::Config { .. }) => None,
+        Err(error) => return Err(error),
+    };
+    let storage = match &db {
+        Some(db) => storage::TaskStorage::new(db)?,
+        None => storage::TaskStorage::in_memory()?,
+    };
+
+    // an unreachable shared database should not make the main one unusable
+    let mut sources = Vec::new();
+    for database in &config.databases {
+        if db.as_ref().is_some_and(|db| database.path.canonicalize().ok() == db.canonicalize().ok()) {
+            continue;
+        }
+        match storage::Source::open(&database.name, &database.path, database.read_only) {
+            Ok(source) => sources.push(source),
+            Err(_) => io.database_unavailable(&database.name, &database.path),
+        }
+    }
+
+    let result = app::run(&storage, &sources, &config, &mut io, cli);
+    // settings skipped while reading are reported once, after the output
+    for (project_id, error) in storage.take_ignored_settings() {
+        io.setting_ignored(None, project_id, &error);
+    }
+    for source in &sources {
+        for (project_id, error) in source.storage.take_ignored_settings() {
+            io.setting_ignored(Some(&source.name), project_id, &error);
+        }
+    }
+    result?;
+    if io.failed() {
+        std::process::exit(1);
+    }
+
+    Ok(())
 }
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use crate::types::{AgendaEntry, Task};
+use super::TaskStorage;
+
+impl TaskStorage {
+    /// Returns unfinished tasks of every project that is not archived due in
+    /// `[from, to)`, soonest first.
+    pub fn get_tasks_due_between(&self, from: i64, to: i64) -> Result<Vec<AgendaEntry>> {
+        Ok(self.conn.prepare(&format!(
+            "SELECT t.id, t.project_id, t.name, t.finished, t.expiration, p.path, {},
+                (SELECT COUNT(*) FROM tasks o WHERE o.project_id = t.project_id AND o.id <= t.id)
+            FROM tasks t
+            JOIN projects p ON p.id = t.project_id
+            WHERE t.finished = 0
+                AND {}
+                AND t.expiration IS NOT NULL
+                AND t.expiration >= ?1
+                AND t.expiration < ?2
+            ORDER BY t.expiration, p.path, t.id",
+            self.schema.project_name_and_color(),
+            if self.schema.archived { "p.archived = 0" } else { "1" },
+        ))?
+            .query_map(params![from, to], |row| {
+                Ok(AgendaEntry {
+                    project_path: row.get(5)?,
+                    project_name: row.get(6)?,
+                    project_color: row.get(7)?,
+                    number: row.get::<_, i64>(8)? as usize,
+                    task: Task {
+                        id: row.get(0)?,
+                        project_id: row.get(1)?,
+                        name: row.get(2)?,
+                        finished: row.get(3)?,
+                        expiration: row.get(4)?,
+                    },
+                    source: None,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
@@ -1,6 +1,23 @@
 mod project;
 
+mod settings;
+
 mod task_storage;
-pub use task_storage::TaskStorage;
+pub use task_storage::{TaskStorage, get_env_storage_path, get_storage_path};
+
+mod source;
+pub use source::Source;
+
+mod profile;
+pub use profile::{DEFAULT_PROFILE, delete_profile, get_profile_path, get_profiles, profile_exists};
 
 mod task;
+
+mod template;
+
+mod search;
+pub use search::{MATCH_END, MATCH_START};
+
+mod agenda;
+
+mod workspace;
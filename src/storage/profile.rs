@@ -0,0 +1,45 @@
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+use crate::utils::is_valid_profile_name;
+use super::task_storage::get_data_dir;
+
+/// Profile name referring to the database used when no profile is selected.
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub fn get_profile_path(name: &str) -> Result<PathBuf> {
+    if !is_valid_profile_name(name) {
+        bail!("Invalid profile name '{}', use letters, digits, '-' and '_'", name);
+    }
+    Ok(get_data_dir()?.join("profiles").join(format!("{name}.db")))
+}
+
+/// Names of all profiles, starting with the default profile.
+pub fn get_profiles() -> Result<Vec<String>> {
+    let mut profiles = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(get_data_dir()?.join("profiles")) {
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().is_none_or(|extension| extension != "db") {
+                continue;
+            }
+            match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) if is_valid_profile_name(name) => profiles.push(name.to_string()),
+                _ => {},
+            }
+        }
+    }
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+
+    Ok(profiles)
+}
+
+pub fn profile_exists(name: &str) -> Result<bool> {
+    Ok(name == DEFAULT_PROFILE || get_profile_path(name)?.exists())
+}
+
+pub fn delete_profile(name: &str) -> Result<()> {
+    std::fs::remove_file(get_profile_path(name)?)?;
+    Ok(())
+}
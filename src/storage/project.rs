@@ -1,17 +1,81 @@
 use anyhow::Result;
-use rusqlite::{OptionalExtension, params};
+use std::path::{Path, PathBuf};
+use rusqlite::{OptionalExtension, Row, params};
+use uuid::Uuid;
 
-use crate::types::Project;
+use crate::types::{Project, ProjectSettings};
 use super::TaskStorage;
+use super::task_storage::GLOBAL_PROJECT;
+
+/// Name of the file marking a directory as a project, holding the project's UUID.
+pub const MARKER_FILE: &str = ".task";
+
+fn project_from_row(row: &Row) -> rusqlite::Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        path: row.get(1)?,
+        uuid: row.get(2)?,
+        name: row.get(3)?,
+        aliases: Vec::new(),
+        archived: row.get(4)?,
+        parent_id: None,
+        settings: ProjectSettings::default(),
+        tasks: Vec::new(),
+        source: None,
+    })
+}
 
 impl TaskStorage {
-    pub fn new_project(&self) -> Result<()> {
+    /// Returns false if the current directory already is a project.
+    pub fn new_project(&self, marker: bool) -> Result<bool> {
+        let cwd = std::env::current_dir()?;
+        // an existing marker, e.g. from a clone on another machine, keeps its identity
+        let uuid = read_marker(&cwd).unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO projects (path, uuid)
+            VALUES (?1, ?2)",
+            params![canonicalize_path(&cwd), uuid],
+        )? > 0;
+
+        if inserted && marker {
+            std::fs::write(cwd.join(MARKER_FILE), format!("{}\n", uuid))?;
+        }
+        Ok(inserted)
+    }
+
+    /// Writes the marker file of the project registered at `dir`, giving the
+    /// project an identity first if it predates markers. Returns false if
+    /// `dir` is not a project.
+    pub fn mark_project(&self, dir: &Path) -> Result<bool> {
+        let Some(project) = self.get_project_by_path(&canonicalize_path(dir))? else {
+            return Ok(false);
+        };
+
+        let uuid = match project.uuid {
+            Some(uuid) => uuid,
+            None => {
+                let uuid = Uuid::new_v4().to_string();
+                self.conn.execute(
+                    "UPDATE projects
+                    SET uuid = ?1
+                    WHERE id = ?2",
+                    params![uuid, project.id],
+                )?;
+                uuid
+            },
+        };
+        std::fs::write(dir.join(MARKER_FILE), format!("{}\n", uuid))?;
+        Ok(true)
+    }
+
+    pub fn new_project_at(&self, path: &Path) -> Result<u32> {
         self.conn.execute(
-            "INSERT INTO projects (path)
-            VALUES (?1)",
-            params![get_cwd()?],
+            "INSERT INTO projects (path, uuid)
+            VALUES (?1, ?2)",
+            params![canonicalize_path(path), Uuid::new_v4().to_string()],
         )?;
-        Ok(())
+        Ok(self.conn.last_insert_rowid() as u32)
     }
 
     pub fn delete_project(&self, id: u32) -> Result<()> {
@@ -23,25 +87,134 @@ impl TaskStorage {
         Ok(())
     }
 
+    /// Attaches the current directory to an existing project.
+    /// Returns false if another project already uses the directory.
+    pub fn relink_project(&self, id: u32) -> Result<bool> {
+        self.move_project(id, &std::env::current_dir()?)
+    }
+
+    /// Returns false if another project already uses `path`.
+    pub fn move_project(&self, id: u32, path: &Path) -> Result<bool> {
+        let path = canonicalize_path(path);
+        if self.get_project_by_path(&path)?.is_some_and(|p| p.id != id) {
+            return Ok(false);
+        }
+
+        self.conn.execute(
+            "UPDATE projects
+            SET path = ?1
+            WHERE id = ?2",
+            params![path, id],
+        )?;
+        Ok(true)
+    }
+
+    pub fn rename_project(&self, id: u32, name: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects
+            SET name = ?1
+            WHERE id = ?2",
+            params![name, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn set_project_archived(&self, id: u32, archived: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE projects
+            SET archived = ?1
+            WHERE id = ?2",
+            params![archived, id],
+        )?;
+        Ok(())
+    }
+
+    pub fn is_project_archived(&self, id: u32) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT archived FROM projects
+            WHERE id = ?1",
+            [id],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Returns false if the alias already exists.
+    pub fn add_project_alias(&self, id: u32, alias: &str) -> Result<bool> {
+        Ok(self.conn.execute(
+            "INSERT OR IGNORE INTO project_aliases (alias, project_id)
+            VALUES (?1, ?2)",
+            params![alias, id],
+        )? > 0)
+    }
+
+    /// Returns false if the alias does not exist.
+    pub fn remove_project_alias(&self, alias: &str) -> Result<bool> {
+        Ok(self.conn.execute(
+            "DELETE FROM project_aliases
+            WHERE alias = ?1",
+            params![alias],
+        )? > 0)
+    }
+
+    fn get_project_aliases(&self, id: u32) -> Result<Vec<String>> {
+        if !self.schema.aliases {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.conn.prepare(
+            "SELECT alias FROM project_aliases
+            WHERE project_id = ?1
+            ORDER BY alias"
+        )?
+            .query_map([id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
     pub fn get_current_project(&self) -> Result<Option<u32>> {
-        self.find_project_by_dir_name(&get_cwd()?)
+        let cwd = PathBuf::from(canonicalize_path(&std::env::current_dir()?));
+        Ok(find_deepest_project(&cwd, &self.get_project_rows()?, read_marker))
+    }
+
+    /// Returns the project identified by the marker file in the current directory.
+    pub fn get_marked_project(&self) -> Result<Option<u32>> {
+        let Some(uuid) = read_marker(&std::env::current_dir()?) else {
+            return Ok(None);
+        };
+
+        Ok(self.conn
+            .query_row(
+                "SELECT id FROM projects
+                WHERE uuid = ?1",
+                [uuid],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub fn get_global_project(&self) -> Result<Option<u32>> {
+        Ok(self.conn
+            .query_row(
+                "SELECT id FROM projects
+                WHERE path = ?1",
+                [GLOBAL_PROJECT],
+                |row| row.get(0),
+            )
+            .optional()?)
     }
 
     pub fn get_project(&self, id: u32) -> Result<Option<Project>> {
-        let project = self.conn.prepare(
-            "SELECT * FROM projects
-            WHERE id = ?1"
-        )?
-            .query_row([id], |row| {
-                Ok(Project {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    tasks: Vec::new(),
-                })
-            })
+        let project = self.conn.prepare(&format!(
+            "SELECT {} FROM projects
+            WHERE id = ?1",
+            self.project_columns(),
+        ))?
+            .query_row([id], project_from_row)
             .optional()?;
 
         if let Some(mut project) = project {
+            project.parent_id = find_parent_project(&project, &self.get_project_rows()?);
+            project.aliases = self.get_project_aliases(project.id)?;
+            project.settings = self.get_project_settings(project.id)?;
             project.tasks = self.get_tasks(project.id)?;
 
             Ok(Some(project))
@@ -50,42 +223,271 @@ impl TaskStorage {
         }
     }
 
+    pub fn get_project_by_path(&self, path: &str) -> Result<Option<Project>> {
+        let project_id = self.conn
+            .query_row(
+                "SELECT id FROM projects
+                WHERE path = ?1",
+                [path],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match project_id {
+            Some(project_id) => self.get_project(project_id),
+            None => Ok(None),
+        }
+    }
+
     pub fn get_all_projects(&self) -> Result<Vec<Project>> {
-        let mut projects = self.conn.prepare(
-            "SELECT id, path
-            FROM projects
-            ORDER BY id"
-        )?
-            .query_map([], |row| {
-                Ok(Project {
-                    id: row.get(0)?,
-                    path: row.get(1)?,
-                    tasks: Vec::new(),
-                })
-            })?
-        .collect::<Result<Vec<_>, _>>()?;
+        let mut projects = self.get_project_rows()?;
 
         for project in &mut projects {
+            project.aliases = self.get_project_aliases(project.id)?;
+            project.settings = self.get_project_settings(project.id)?;
             project.tasks = self.get_tasks(project.id)?;
         }
 
         Ok(projects)
     }
 
-    pub fn find_project_by_dir_name(&self, name: &str) -> Result<Option<u32>> {
-        Ok(self.conn
-            .query_row(
-                "SELECT id FROM projects
-                WHERE path = ?1 OR path LIKE ?2
-                ORDER BY LENGTH(path) DESC
-                LIMIT 1",
-                [name, &format!("%/{}", name)],
-                |row| row.get(0),
-            )
-            .optional()?)
+    /// Returns the projects nested inside a project, parents before their
+    /// children.
+    pub fn get_sub_projects(&self, id: u32) -> Result<Vec<Project>> {
+        let mut sub_projects = Vec::new();
+        collect_sub_projects(id, self.get_all_projects()?, &mut sub_projects);
+
+        Ok(sub_projects)
+    }
+
+    /// Returns all projects without loading their tasks.
+    fn get_project_rows(&self) -> Result<Vec<Project>> {
+        let mut projects = self.conn.prepare(&format!(
+            "SELECT {}
+            FROM projects
+            ORDER BY id",
+            self.project_columns(),
+        ))?
+            .query_map([], project_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let parents: Vec<_> = projects.iter().map(|p| find_parent_project(p, &projects)).collect();
+        for (project, parent_id) in projects.iter_mut().zip(parents) {
+            project.parent_id = parent_id;
+        }
+
+        Ok(projects)
+    }
+
+    /// Returns every project `name` could refer to. Names and aliases are
+    /// unique, so only directory names can match several projects.
+    pub fn find_projects_by_dir_name(&self, name: &str) -> Result<Vec<Project>> {
+        let named = self.query_projects(
+            "WHERE id IN (
+                SELECT id FROM projects WHERE name = ?1
+                UNION
+                SELECT project_id FROM project_aliases WHERE alias = ?1
+            )",
+            name,
+        )?;
+        if !named.is_empty() {
+            return Ok(named);
+        }
+
+        // anything that looks like a path is matched by its canonical form
+        let path = if name.contains('/') {
+            canonicalize_path(Path::new(name))
+        } else {
+            name.to_string()
+        };
+        let exact = self.query_projects("WHERE path = ?1", &path)?;
+        if !exact.is_empty() {
+            return Ok(exact);
+        }
+
+        self.query_projects(
+            "WHERE substr(path, -length(?1)) = ?1",
+            &format!("/{}", name.trim_end_matches('/')),
+        )
+    }
+
+    /// Every name a project can be referred to by, for suggestions.
+    pub fn get_project_names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for project in self.get_project_rows()? {
+            names.extend(project.name);
+            if let Some(dir_name) = Path::new(&project.path).file_name() {
+                names.push(dir_name.to_string_lossy().to_string());
+            }
+        }
+        if self.schema.aliases {
+            names.extend(
+                self.conn.prepare("SELECT alias FROM project_aliases")?
+                    .query_map([], |row| row.get(0))?
+                    .collect::<Result<Vec<String>, _>>()?
+            );
+        }
+        names.sort();
+        names.dedup();
+
+        Ok(names)
     }
+
+    /// The columns read by `project_from_row`, with defaults for those a
+    /// database created by an older version lacks.
+    fn project_columns(&self) -> String {
+        format!(
+            "id, path, {}, {}, {}",
+            if self.schema.uuid { "uuid" } else { "NULL" },
+            if self.schema.name { "name" } else { "NULL" },
+            if self.schema.archived { "archived" } else { "0" },
+        )
+    }
+
+    fn query_projects(&self, condition: &str, param: &str) -> Result<Vec<Project>> {
+        Ok(self.conn.prepare(&format!(
+            "SELECT {}
+            FROM projects
+            {condition}
+            ORDER BY path",
+            self.project_columns(),
+        ))?
+            .query_map([param], project_from_row)?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
+
+/// Resolves symlinks, `.`, `..` and trailing slashes so every directory has a
+/// single stored path. Paths that do not exist are only made absolute.
+pub fn canonicalize_path(path: &Path) -> String {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    std::fs::canonicalize(&absolute)
+        .unwrap_or_else(|_| absolute.components().collect())
+        .to_string_lossy()
+        .to_string()
+}
+
+fn read_marker(dir: &Path) -> Option<String> {
+    let uuid = std::fs::read_to_string(dir.join(MARKER_FILE)).ok()?;
+    let uuid = uuid.trim();
+    (!uuid.is_empty()).then(|| uuid.to_string())
 }
 
-fn get_cwd() -> Result<String> {
-    Ok(std::env::current_dir()?.to_string_lossy().to_string())
+/// Returns the project registered at `dir` or at its closest ancestor. At each
+/// level a marker file takes precedence over the registered path, so moved
+/// directories keep their project.
+fn find_deepest_project(
+    dir: &Path,
+    projects: &[Project],
+    read_marker: impl Fn(&Path) -> Option<String>,
+) -> Option<u32> {
+    dir.ancestors().find_map(|ancestor| {
+        let marked = read_marker(ancestor).and_then(|uuid| {
+            projects.iter().find(|p| p.uuid.as_deref() == Some(uuid.as_str()))
+        });
+
+        marked
+            .or_else(|| projects.iter().find(|p| Path::new(&p.path) == ancestor))
+            .map(|p| p.id)
+    })
+}
+
+fn collect_sub_projects(parent_id: u32, projects: Vec<Project>, sub_projects: &mut Vec<Project>) -> Vec<Project> {
+    let (children, mut rest): (Vec<_>, Vec<_>) = projects
+        .into_iter()
+        .partition(|p| p.parent_id == Some(parent_id));
+
+    for child in children {
+        let child_id = child.id;
+        sub_projects.push(child);
+        rest = collect_sub_projects(child_id, rest, sub_projects);
+    }
+
+    rest
+}
+
+fn find_parent_project(project: &Project, projects: &[Project]) -> Option<u32> {
+    let dir = Path::new(&project.path).parent()?;
+    find_deepest_project(dir, projects, |_| None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(id: u32, path: &str) -> Project {
+        Project { id, path: path.to_string(), ..Default::default() }
+    }
+
+    fn projects() -> Vec<Project> {
+        vec![
+            project(1, "global"),
+            project(2, "/home/user/repo"),
+            project(3, "/home/user/repo/services/api"),
+            project(4, "/home/user/web"),
+        ]
+    }
+
+    fn no_marker(_: &Path) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn matches_project_directory() {
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo"), &projects(), no_marker), Some(2));
+    }
+
+    #[test]
+    fn matches_enclosing_project_from_subdirectory() {
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/src/storage"), &projects(), no_marker), Some(2));
+    }
+
+    #[test]
+    fn prefers_deepest_nested_project() {
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/services/api"), &projects(), no_marker), Some(3));
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/services/api/src"), &projects(), no_marker), Some(3));
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/services"), &projects(), no_marker), Some(2));
+    }
+
+    #[test]
+    fn ignores_paths_sharing_a_string_prefix() {
+        assert_eq!(find_deepest_project(Path::new("/home/user/repository"), &projects(), no_marker), None);
+        assert_eq!(find_deepest_project(Path::new("/home/user/website/src"), &projects(), no_marker), None);
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/services/api-gateway"), &projects(), no_marker), Some(2));
+    }
+
+    #[test]
+    fn returns_none_outside_projects() {
+        assert_eq!(find_deepest_project(Path::new("/home/user"), &projects(), no_marker), None);
+        assert_eq!(find_deepest_project(Path::new("/"), &projects(), no_marker), None);
+    }
+
+    #[test]
+    fn ignores_trailing_slashes() {
+        let projects = vec![project(1, "/home/user/repo/")];
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/src"), &projects, no_marker), Some(1));
+    }
+
+    #[test]
+    fn prefers_marker_over_registered_path() {
+        let mut projects = projects();
+        projects[3].uuid = Some("2f1c".to_string());
+        let marker = |dir: &Path| (dir == Path::new("/home/user/moved")).then(|| "2f1c".to_string());
+
+        assert_eq!(find_deepest_project(Path::new("/home/user/moved/src"), &projects, marker), Some(4));
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/src"), &projects, marker), Some(2));
+    }
+
+    #[test]
+    fn ignores_unknown_marker() {
+        let marker = |dir: &Path| (dir == Path::new("/home/user/repo/src")).then(|| "unknown".to_string());
+        assert_eq!(find_deepest_project(Path::new("/home/user/repo/src"), &projects(), marker), Some(2));
+    }
+
+    #[test]
+    fn derives_parent_from_path_containment() {
+        let projects = projects();
+        let parents: Vec<_> = projects.iter().map(|p| find_parent_project(p, &projects)).collect();
+        assert_eq!(parents, vec![None, None, Some(2), None]);
+    }
 }
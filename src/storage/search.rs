@@ -0,0 +1,169 @@
+use anyhow::Result;
+use rusqlite::{Row, params, params_from_iter};
+use std::ops::Range;
+
+use crate::types::{SearchResult, Task};
+use super::TaskStorage;
+use super::task_storage::has_table;
+
+/// Marks the start of a matched term in `SearchResult::snippet`.
+pub const MATCH_START: char = '\u{2}';
+/// Marks the end of a matched term in `SearchResult::snippet`.
+pub const MATCH_END: char = '\u{3}';
+
+impl TaskStorage {
+    pub(super) fn init_search_index(&self) -> Result<()> {
+        let exists = has_table(&self.conn, "tasks_fts")?;
+
+        self.conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS tasks_fts
+                USING fts5(name, content='tasks', content_rowid='id');
+
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_insert AFTER INSERT ON tasks BEGIN
+                INSERT INTO tasks_fts (rowid, name) VALUES (new.id, new.name);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_delete AFTER DELETE ON tasks BEGIN
+                INSERT INTO tasks_fts (tasks_fts, rowid, name) VALUES ('delete', old.id, old.name);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS tasks_fts_update AFTER UPDATE OF name ON tasks BEGIN
+                INSERT INTO tasks_fts (tasks_fts, rowid, name) VALUES ('delete', old.id, old.name);
+                INSERT INTO tasks_fts (rowid, name) VALUES (new.id, new.name);
+            END;"
+        )?;
+
+        // index tasks created before the search index existed
+        if !exists {
+            self.conn.execute(
+                "INSERT INTO tasks_fts (tasks_fts) VALUES ('rebuild')",
+                [],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn search_tasks(&self, query: &str) -> Result<Vec<SearchResult>> {
+        if !self.schema.search_index {
+            return self.search_task_names(query);
+        }
+
+        let query = to_fts_query(query);
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.conn.prepare(&format!(
+            "SELECT t.id, t.project_id, t.name, t.finished, t.expiration, p.path, {},
+                (SELECT COUNT(*) FROM tasks o WHERE o.project_id = t.project_id AND o.id <= t.id),
+                snippet(tasks_fts, 0, ?2, ?3, '…', 12)
+            FROM tasks_fts
+            JOIN tasks t ON t.id = tasks_fts.rowid
+            JOIN projects p ON p.id = t.project_id
+            WHERE tasks_fts MATCH ?1
+            ORDER BY p.path, t.id",
+            self.schema.project_name_and_color(),
+        ))?
+            .query_map(params![query, MATCH_START.to_string(), MATCH_END.to_string()], search_result_from_row)?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Searches a database created before the search index existed, for tasks
+    /// whose name contains every word.
+    fn search_task_names(&self, query: &str) -> Result<Vec<SearchResult>> {
+        let words = query.split_whitespace().collect::<Vec<_>>();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conditions = (1..=words.len())
+            .map(|index| format!("t.name LIKE ?{index} ESCAPE '\\'"))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let patterns = words
+            .iter()
+            .map(|word| format!("%{}%", word.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")));
+
+        let mut results = self.conn.prepare(&format!(
+            "SELECT t.id, t.project_id, t.name, t.finished, t.expiration, p.path, {},
+                (SELECT COUNT(*) FROM tasks o WHERE o.project_id = t.project_id AND o.id <= t.id),
+                t.name
+            FROM tasks t
+            JOIN projects p ON p.id = t.project_id
+            WHERE {conditions}
+            ORDER BY p.path, t.id",
+            self.schema.project_name_and_color(),
+        ))?
+            .query_map(params_from_iter(patterns), search_result_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for result in &mut results {
+            result.snippet = mark_matches(&result.snippet, &words);
+        }
+
+        Ok(results)
+    }
+}
+
+fn search_result_from_row(row: &Row) -> rusqlite::Result<SearchResult> {
+    Ok(SearchResult {
+        project_path: row.get(5)?,
+        project_name: row.get(6)?,
+        project_color: row.get(7)?,
+        number: row.get::<_, i64>(8)? as usize,
+        snippet: row.get(9)?,
+        task: Task {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            name: row.get(2)?,
+            finished: row.get(3)?,
+            expiration: row.get(4)?,
+        },
+        source: None,
+    })
+}
+
+/// Surrounds every occurrence of `words` in `text` with `MATCH_START` and
+/// `MATCH_END` like `snippet()`, ignoring ASCII case like `LIKE` does.
+fn mark_matches(text: &str, words: &[&str]) -> String {
+    // ASCII lowercasing keeps byte offsets valid in `text`
+    let lowercase = text.to_ascii_lowercase();
+    let mut ranges = Vec::new();
+    for word in words {
+        let word = word.to_ascii_lowercase();
+        ranges.extend(lowercase.match_indices(&word).map(|(start, _)| start..start + word.len()));
+    }
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    let mut marked = String::new();
+    let mut position = 0;
+    for range in merged {
+        marked.push_str(&text[position..range.start]);
+        marked.push(MATCH_START);
+        marked.push_str(&text[range.clone()]);
+        marked.push(MATCH_END);
+        position = range.end;
+    }
+    marked.push_str(&text[position..]);
+
+    marked
+}
+
+/// Quotes every word so user input is never parsed as FTS5 syntax, and
+/// prefix-matches each of them so partial words still find tasks.
+fn to_fts_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
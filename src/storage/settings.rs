@@ -0,0 +1,50 @@
+use anyhow::Result;
+use rusqlite::params;
+
+use crate::types::ProjectSettings;
+use super::TaskStorage;
+
+impl TaskStorage {
+    pub fn set_project_setting(&self, project_id: u32, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO project_settings (project_id, key, value)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT (project_id, key) DO UPDATE SET value = excluded.value",
+            params![project_id, key, value],
+        )?;
+        Ok(())
+    }
+
+    pub fn unset_project_setting(&self, project_id: u32, key: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM project_settings
+            WHERE project_id = ?1 AND key = ?2",
+            params![project_id, key],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_project_settings(&self, project_id: u32) -> Result<ProjectSettings> {
+        if !self.schema.settings {
+            return Ok(ProjectSettings::default());
+        }
+
+        let entries = self.conn.prepare(
+            "SELECT key, value FROM project_settings
+            WHERE project_id = ?1"
+        )?
+            .query_map([project_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // a value written by another version, e.g. into a shared database,
+        // should not make every listing fail
+        let mut settings = ProjectSettings::default();
+        for (key, value) in entries {
+            if let Err(error) = settings.set(&key, Some(&value)) {
+                self.ignore_setting(project_id, &error);
+            }
+        }
+
+        Ok(settings)
+    }
+}
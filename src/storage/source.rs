@@ -0,0 +1,23 @@
+use anyhow::Result;
+use std::path::Path;
+
+use super::TaskStorage;
+
+/// An additional database whose tasks are listed alongside those of the main
+/// database, labeled with its name.
+pub struct Source {
+    pub name: String,
+    pub storage: TaskStorage,
+}
+
+impl Source {
+    pub fn open(name: &str, path: &Path, read_only: bool) -> Result<Self> {
+        let storage = if read_only {
+            TaskStorage::open_read_only(path)?
+        } else {
+            TaskStorage::new(path)?
+        };
+
+        Ok(Self { name: name.to_string(), storage })
+    }
+}
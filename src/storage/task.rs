@@ -9,7 +9,7 @@ impl TaskStorage {
         &self,
         project_id: u32,
         name: &str,
-        expiration: Option<&str>,
+        expiration: Option<i64>,
     ) -> Result<()> {
         self.conn.execute(
             "INSERT INTO tasks (project_id, name, finished, expiration)
@@ -57,6 +57,40 @@ impl TaskStorage {
         Ok(())
     }
 
+    pub fn move_task(&self, task_id: u32, project_id: u32) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tasks
+            SET project_id = ?1
+            WHERE id = ?2",
+            params![project_id, task_id],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn reassign_tasks(&self, from_project_id: u32, to_project_id: u32) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tasks
+            SET project_id = ?1
+            WHERE project_id = ?2",
+            params![to_project_id, from_project_id],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn copy_task(&self, task_id: u32, project_id: u32) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO tasks (project_id, name, finished, expiration)
+            SELECT ?1, name, finished, expiration
+            FROM tasks
+            WHERE id = ?2",
+            params![project_id, task_id],
+        )?;
+
+        Ok(())
+    }
+
     pub fn get_task(&self, task_id: u32) -> Result<Task> {
         Ok(self.conn.prepare(
             "SELECT * FROM tasks
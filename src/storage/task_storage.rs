@@ -1,14 +1,29 @@
-use anyhow::Result;
-use rusqlite::Connection;
-use std::path::PathBuf;
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, OpenFlags};
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::profile::{DEFAULT_PROFILE, get_profile_path};
+
+/// Path of the project holding tasks that are not tied to a directory.
+pub const GLOBAL_PROJECT: &str = "global";
 
 pub struct TaskStorage {
     pub conn: Connection,
+    pub(super) schema: Schema,
+    /// Stored project settings that could not be read, see `take_ignored_settings`.
+    ignored_settings: RefCell<BTreeSet<(u32, String)>>,
 }
 
 impl TaskStorage {
-    pub fn new() -> Result<Self> {
-        let conn = Connection::open(get_storage_path()?)?;
+    pub fn new(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
         conn.execute(
             "PRAGMA foreign_keys = ON",
             [],
@@ -20,6 +35,40 @@ impl TaskStorage {
             )",
             [],
         )?;
+        add_column_if_missing(&conn, "projects", "uuid", "TEXT")?;
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS projects_uuid ON projects(uuid)",
+            [],
+        )?;
+        add_column_if_missing(&conn, "projects", "name", "TEXT")?;
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS projects_name ON projects(name)",
+            [],
+        )?;
+        add_column_if_missing(&conn, "projects", "archived", "INTEGER NOT NULL DEFAULT 0")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS project_aliases(
+                alias TEXT PRIMARY KEY,
+                project_id INTEGER NOT NULL,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO projects (path)
+            VALUES (?1)",
+            [GLOBAL_PROJECT],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS project_settings(
+                project_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                PRIMARY KEY (project_id, key),
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
         conn.execute(
             "CREATE TABLE IF NOT EXISTS tasks(
                 id INTEGER PRIMARY KEY,
@@ -31,16 +80,172 @@ impl TaskStorage {
             )",
             [],
         )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS templates(
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS template_tasks(
+                id INTEGER PRIMARY KEY,
+                template_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                due_offset INTEGER,
+                FOREIGN KEY (template_id) REFERENCES templates(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workspaces(
+                id INTEGER PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workspace_projects(
+                workspace_id INTEGER NOT NULL,
+                project_id INTEGER NOT NULL,
+                PRIMARY KEY (workspace_id, project_id),
+                FOREIGN KEY (workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE,
+                FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        let storage = Self { conn, schema: Schema::CURRENT, ignored_settings: RefCell::default() };
+        storage.init_search_index()?;
+
+        Ok(storage)
+    }
+}
+
+impl TaskStorage {
+    /// An empty database that is discarded on exit.
+    pub fn in_memory() -> Result<Self> {
+        // SQLite treats this file name specially
+        Self::new(Path::new(":memory:"))
+    }
+
+    /// Opens an existing database without creating or upgrading its schema.
+    pub fn open_read_only(path: &Path) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
+
+        Self::from_connection(conn)
+    }
+
+    /// Wraps a connection without changing its schema. Read queries leave out
+    /// what a database created by an older version lacks.
+    pub fn from_connection(conn: Connection) -> Result<Self> {
+        let schema = Schema::detect(&conn)?;
+        Ok(Self { conn, schema, ignored_settings: RefCell::default() })
+    }
+
+    /// Project ids and errors of the settings skipped while reading projects
+    /// since the last call.
+    pub fn take_ignored_settings(&self) -> BTreeSet<(u32, String)> {
+        self.ignored_settings.take()
+    }
+
+    pub(super) fn ignore_setting(&self, project_id: u32, error: &anyhow::Error) {
+        self.ignored_settings.borrow_mut().insert((project_id, error.to_string()));
+    }
+}
+
+/// Parts of the schema added after the first release.
+#[derive(Clone, Copy)]
+pub(super) struct Schema {
+    pub uuid: bool,
+    pub name: bool,
+    pub archived: bool,
+    pub aliases: bool,
+    pub settings: bool,
+    pub search_index: bool,
+}
+
+impl Schema {
+    const CURRENT: Schema = Schema { uuid: true, name: true, archived: true, aliases: true, settings: true, search_index: true };
+
+    /// Name and color of the project joined as `p`, or `NULL` where the
+    /// database lacks them.
+    pub fn project_name_and_color(&self) -> String {
+        let name = if self.name { "p.name" } else { "NULL" };
+        let color = match self.settings {
+            true => "(SELECT value FROM project_settings s WHERE s.project_id = p.id AND s.key = 'color')",
+            false => "NULL",
+        };
+        format!("{name}, {color}")
+    }
+
+    fn detect(conn: &Connection) -> Result<Self> {
+        Ok(Self {
+            uuid: has_column(conn, "projects", "uuid")?,
+            name: has_column(conn, "projects", "name")?,
+            archived: has_column(conn, "projects", "archived")?,
+            aliases: has_table(conn, "project_aliases")?,
+            settings: has_table(conn, "project_settings")?,
+            search_index: has_table(conn, "tasks_fts")?,
+        })
+    }
+}
+
+pub(super) fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1)
+        WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?)
+}
+
+pub(super) fn has_table(conn: &Connection, table: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master
+        WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )?)
+}
+
+/// Brings databases created by older versions up to date with the current schema.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}"), [])?;
+    }
+
+    Ok(())
+}
+
+/// `TASK_DB`, if set.
+pub fn get_env_storage_path() -> Option<PathBuf> {
+    std::env::var_os("TASK_DB").filter(|path| !path.is_empty()).map(PathBuf::from)
+}
 
-        Ok(Self { conn })
+/// The database of `profile` in the `task` data directory.
+pub fn get_storage_path(profile: Option<&str>) -> Result<PathBuf> {
+    match profile {
+        Some(profile) if profile != DEFAULT_PROFILE => {
+            let path = get_profile_path(profile)?;
+            if !path.exists() {
+                bail!("Profile '{}' does not exist, create it with `task profile create {}`", profile, profile);
+            }
+            Ok(path)
+        },
+        _ => Ok(get_data_dir()?.join("task.db")),
     }
 }
 
-fn get_storage_path() -> Result<String> {
-    let base_dir = std::env::var("XDG_DATA_HOME")
-        .unwrap_or_else(|_| format!("{}/.local/share", std::env::var("HOME").unwrap()));
-    let path = PathBuf::from(base_dir).join("task");
-    std::fs::create_dir_all(&path)?;
+pub fn get_data_dir() -> Result<PathBuf> {
+    let base_dir = match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => match dirs::data_dir() {
+            Some(dir) => dir,
+            None => bail!("Could not determine where to store the database, pass --db <PATH> or set TASK_DB"),
+        },
+    };
 
-    Ok(path.join("task.db").to_string_lossy().to_string())
+    Ok(base_dir.join("task"))
 }
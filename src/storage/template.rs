@@ -0,0 +1,103 @@
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+
+use crate::types::{Task, Template, TemplateTask};
+use super::TaskStorage;
+
+impl TaskStorage {
+    pub fn save_template(&self, name: &str, tasks: &[Task], anchor: i64) -> Result<()> {
+        // saving under an existing name replaces the old template
+        self.conn.execute(
+            "DELETE FROM templates
+            WHERE name = ?1",
+            params![name],
+        )?;
+        self.conn.execute(
+            "INSERT INTO templates (name)
+            VALUES (?1)",
+            params![name],
+        )?;
+        let template_id = self.conn.last_insert_rowid();
+
+        for task in tasks {
+            self.conn.execute(
+                "INSERT INTO template_tasks (template_id, name, due_offset)
+                VALUES (?1, ?2, ?3)",
+                params![template_id, task.name, task.expiration.map(|exp| exp - anchor)],
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn delete_template(&self, id: u32) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM templates
+            WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_template(&self, name: &str) -> Result<Option<Template>> {
+        let template = self.conn.prepare(
+            "SELECT id, name FROM templates
+            WHERE name = ?1"
+        )?
+            .query_row([name], |row| {
+                Ok(Template {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    tasks: Vec::new(),
+                })
+            })
+            .optional()?;
+
+        if let Some(mut template) = template {
+            template.tasks = self.get_template_tasks(template.id)?;
+
+            Ok(Some(template))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_all_templates(&self) -> Result<Vec<Template>> {
+        let mut templates = self.conn.prepare(
+            "SELECT id, name
+            FROM templates
+            ORDER BY name"
+        )?
+            .query_map([], |row| {
+                Ok(Template {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    tasks: Vec::new(),
+                })
+            })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        for template in &mut templates {
+            template.tasks = self.get_template_tasks(template.id)?;
+        }
+
+        Ok(templates)
+    }
+
+    fn get_template_tasks(&self, template_id: u32) -> Result<Vec<TemplateTask>> {
+        Ok(self.conn.prepare(
+            "SELECT id, template_id, name, due_offset FROM template_tasks
+            WHERE template_id = ?1
+            ORDER BY id"
+        )?
+            .query_map([template_id], |row| {
+                Ok(TemplateTask {
+                    id: row.get(0)?,
+                    template_id: row.get(1)?,
+                    name: row.get(2)?,
+                    due_offset: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use rusqlite::{OptionalExtension, params};
+
+use crate::types::{Project, Workspace};
+use super::TaskStorage;
+
+impl TaskStorage {
+    /// Returns false if a workspace with the name already exists.
+    pub fn create_workspace(&self, name: &str) -> Result<bool> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO workspaces (name)
+            VALUES (?1)",
+            params![name],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn delete_workspace(&self, id: u32) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM workspaces
+            WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_workspace_project(&self, workspace_id: u32, project_id: u32) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO workspace_projects (workspace_id, project_id)
+            VALUES (?1, ?2)",
+            params![workspace_id, project_id],
+        )?;
+        Ok(())
+    }
+
+    /// Returns false if the project was not a member of the workspace.
+    pub fn remove_workspace_project(&self, workspace_id: u32, project_id: u32) -> Result<bool> {
+        let removed = self.conn.execute(
+            "DELETE FROM workspace_projects
+            WHERE workspace_id = ?1 AND project_id = ?2",
+            params![workspace_id, project_id],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_workspace(&self, name: &str) -> Result<Option<Workspace>> {
+        let workspace = self.conn.prepare(
+            "SELECT id, name FROM workspaces
+            WHERE name = ?1"
+        )?
+            .query_row([name], |row| {
+                Ok(Workspace {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    projects: Vec::new(),
+                })
+            })
+            .optional()?;
+
+        if let Some(mut workspace) = workspace {
+            workspace.projects = self.get_workspace_projects(workspace.id)?;
+
+            Ok(Some(workspace))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn get_all_workspaces(&self) -> Result<Vec<Workspace>> {
+        let mut workspaces = self.conn.prepare(
+            "SELECT id, name
+            FROM workspaces
+            ORDER BY name"
+        )?
+            .query_map([], |row| {
+                Ok(Workspace {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    projects: Vec::new(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for workspace in &mut workspaces {
+            workspace.projects = self.get_workspace_projects(workspace.id)?;
+        }
+
+        Ok(workspaces)
+    }
+
+    fn get_workspace_projects(&self, workspace_id: u32) -> Result<Vec<Project>> {
+        let project_ids = self.conn.prepare(
+            "SELECT w.project_id
+            FROM workspace_projects w
+            JOIN projects p ON p.id = w.project_id
+            WHERE w.workspace_id = ?1
+            ORDER BY p.path"
+        )?
+            .query_map([workspace_id], |row| row.get::<_, u32>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut projects = Vec::new();
+        for project_id in project_ids {
+            projects.extend(self.get_project(project_id)?);
+        }
+
+        Ok(projects)
+    }
+}
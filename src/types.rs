@@ -1,4 +1,9 @@
+use anyhow::{Result, bail};
+use colored::Color;
 use serde::{Serialize, Deserialize};
+use std::path::Path;
+
+use crate::utils::{parse_duration, project_label};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Task {
@@ -13,5 +18,137 @@ pub struct Task {
 pub struct Project {
     pub id: u32,
     pub path: String,
+    pub uuid: Option<String>,
+    pub name: Option<String>,
+    pub aliases: Vec<String>,
+    pub archived: bool,
+    /// The closest other project containing this one's directory.
+    pub parent_id: Option<u32>,
+    pub settings: ProjectSettings,
     pub tasks: Vec<Task>,
+    /// Name of the additional database the project was read from, if any.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    pub description: Option<String>,
+    /// Due date offset, such as `1w`, given to new tasks without one.
+    pub default_due: Option<String>,
+    /// Color of the project in output covering several projects.
+    pub color: Option<String>,
+    pub hide_finished: Option<bool>,
+}
+
+impl ProjectSettings {
+    pub const KEYS: [&str; 4] = ["description", "default_due", "color", "hide_finished"];
+
+    /// Sets `key` to `value`, or resets it with `None`.
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        match key {
+            "description" => self.description = value.map(str::to_string),
+            "default_due" => {
+                if let Some(value) = value && parse_duration(value).is_none() {
+                    bail!("Invalid value '{}' for default_due, expected a relative time such as `2w4d`", value);
+                }
+                self.default_due = value.map(str::to_string);
+            },
+            "color" => {
+                if let Some(value) = value && value.parse::<Color>().is_err() {
+                    bail!("Invalid value '{}' for color, expected a color name such as `blue` or `bright magenta`", value);
+                }
+                self.color = value.map(str::to_string);
+            },
+            "hide_finished" => {
+                self.hide_finished = match value {
+                    Some(value) => match value.parse() {
+                        Ok(value) => Some(value),
+                        Err(_) => bail!("Invalid value '{}' for hide_finished, expected `true` or `false`", value),
+                    },
+                    None => None,
+                };
+            },
+            _ => bail!("Unknown setting '{}', expected one of: {}", key, Self::KEYS.join(", ")),
+        }
+
+        Ok(())
+    }
+
+    /// The settings that are set, as key and value pairs.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        [
+            ("description", self.description.clone()),
+            ("default_due", self.default_due.clone()),
+            ("color", self.color.clone()),
+            ("hide_finished", self.hide_finished.map(|value| value.to_string())),
+        ]
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect()
+    }
+}
+
+impl Project {
+    pub fn label(&self) -> String {
+        project_label(self.name.as_deref(), &self.path)
+    }
+
+    /// Label of a sub-project shown beneath `ancestor`: its name, or its path
+    /// relative to the ancestor.
+    pub fn label_within(&self, ancestor: &Project) -> String {
+        match (&self.name, Path::new(&self.path).strip_prefix(&ancestor.path)) {
+            (None, Ok(relative)) => relative.display().to_string(),
+            _ => self.label(),
+        }
+    }
+
+    /// Whether the directory of the project no longer exists. The global
+    /// project has no directory and is never missing.
+    pub fn is_missing(&self) -> bool {
+        Path::new(&self.path).is_absolute() && !Path::new(&self.path).is_dir()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateTask {
+    pub id: u32,
+    pub template_id: u32,
+    pub name: String,
+    pub due_offset: Option<i64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Template {
+    pub id: u32,
+    pub name: String,
+    pub tasks: Vec<TemplateTask>,
+}
+
+/// A named group of projects listed together, such as related repositories.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: u32,
+    pub name: String,
+    pub projects: Vec<Project>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub project_path: String,
+    pub project_name: Option<String>,
+    pub project_color: Option<String>,
+    pub number: usize,
+    pub snippet: String,
+    pub task: Task,
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgendaEntry {
+    pub project_path: String,
+    pub project_name: Option<String>,
+    pub project_color: Option<String>,
+    pub number: usize,
+    pub task: Task,
+    pub source: Option<String>,
 }
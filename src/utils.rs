@@ -1,22 +1,14 @@
 use chrono::{NaiveDate, NaiveDateTime, Utc, Duration, Datelike, Local, TimeZone};
 use regex::Regex;
+use crate::config::DateOrder;
+use std::path::{Path, PathBuf};
 
-pub fn parse_to_unix(input: &str) -> Option<i64> {
+pub fn parse_to_unix(input: &str, date_order: DateOrder) -> Option<i64> {
     let now = Local::now();
     
     // relative durations
-    let re_relative = Regex::new(r"(?i)(?:(\d+)y)?(?:(\d+)m)?(?:(\d+)w)?(?:(\d+)d)?(?:(\d+)h)?(?:(\d+)min)?$").unwrap();
-    if let Some(caps) = re_relative.captures(input) {
-        let mut duration = Duration::seconds(0);
-        if let Some(y) = caps.get(1) { duration += Duration::days(y.as_str().parse::<i64>().unwrap() * 365); }
-        if let Some(m) = caps.get(2) { duration += Duration::days(m.as_str().parse::<i64>().unwrap() * 30); }
-        if let Some(w) = caps.get(3) { duration += Duration::days(w.as_str().parse::<i64>().unwrap() * 7); }
-        if let Some(d) = caps.get(4) { duration += Duration::days(d.as_str().parse::<i64>().unwrap()); }
-        if let Some(h) = caps.get(5) { duration += Duration::hours(h.as_str().parse::<i64>().unwrap()); }
-        if let Some(min) = caps.get(6) { duration += Duration::minutes(min.as_str().parse::<i64>().unwrap()); }
-        if duration != Duration::seconds(0) {
-            return Some((now + duration).timestamp());
-        }
+    if let Some(duration) = parse_duration(input) {
+        return Some((now + duration).timestamp());
     }
 
     // time only: HH:MM (today, local)
@@ -30,12 +22,17 @@ pub fn parse_to_unix(input: &str) -> Option<i64> {
         return Some(local_dt.timestamp());
     }
 
-    // absolute date/time format: DDMM[YY][-HH:MM], local
-    let re_abs = Regex::new(r"^(\d{2})(\d{2})(\d{2})?(?:-(\d{2}):(\d{2}))?$").unwrap();
+    // absolute date/time format: DDMM[YY][-HH:MM] by default, local
+    let (re_abs, day_group, month_group, year_group) = match date_order {
+        DateOrder::Dmy => (r"^(\d{2})(\d{2})(\d{2})?(?:-(\d{2}):(\d{2}))?$", 1, 2, 3),
+        DateOrder::Mdy => (r"^(\d{2})(\d{2})(\d{2})?(?:-(\d{2}):(\d{2}))?$", 2, 1, 3),
+        DateOrder::Ymd => (r"^(\d{2})?(\d{2})(\d{2})(?:-(\d{2}):(\d{2}))?$", 3, 2, 1),
+    };
+    let re_abs = Regex::new(re_abs).unwrap();
     if let Some(caps) = re_abs.captures(input) {
-        let day = caps.get(1).unwrap().as_str().parse::<u32>().ok()?;
-        let month = caps.get(2).unwrap().as_str().parse::<u32>().ok()?;
-        let year = if let Some(y) = caps.get(3) {
+        let day = caps.get(day_group).unwrap().as_str().parse::<u32>().ok()?;
+        let month = caps.get(month_group).unwrap().as_str().parse::<u32>().ok()?;
+        let year = if let Some(y) = caps.get(year_group) {
             2000 + y.as_str().parse::<i32>().ok()?  // assuming 2000+
         } else {
             now.year()
@@ -52,6 +49,21 @@ pub fn parse_to_unix(input: &str) -> Option<i64> {
     None
 }
 
+/// Parses a relative duration such as `2w4d`.
+pub fn parse_duration(input: &str) -> Option<Duration> {
+    let re_relative = Regex::new(r"(?i)^(?:(\d+)y)?(?:(\d+)m)?(?:(\d+)w)?(?:(\d+)d)?(?:(\d+)h)?(?:(\d+)min)?$").unwrap();
+    let caps = re_relative.captures(input)?;
+    let mut duration = Duration::seconds(0);
+    if let Some(y) = caps.get(1) { duration += Duration::days(y.as_str().parse::<i64>().ok()? * 365); }
+    if let Some(m) = caps.get(2) { duration += Duration::days(m.as_str().parse::<i64>().ok()? * 30); }
+    if let Some(w) = caps.get(3) { duration += Duration::days(w.as_str().parse::<i64>().ok()? * 7); }
+    if let Some(d) = caps.get(4) { duration += Duration::days(d.as_str().parse::<i64>().ok()?); }
+    if let Some(h) = caps.get(5) { duration += Duration::hours(h.as_str().parse::<i64>().ok()?); }
+    if let Some(min) = caps.get(6) { duration += Duration::minutes(min.as_str().parse::<i64>().ok()?); }
+
+    (duration != Duration::seconds(0)).then_some(duration)
+}
+
 pub fn unix_to_relative(unix_time: i64) -> String {
     let now = Utc::now().timestamp();
     let mut seconds = unix_time - now;
@@ -121,3 +133,182 @@ pub fn unix_to_relative(unix_time: i64) -> String {
         output
     }
 }
+
+/// Unix time of local midnight starting the day `days` from today.
+pub fn start_of_day(days: i64) -> i64 {
+    local_midnight(Local::now().date_naive() + Duration::days(days))
+}
+
+/// Unix time of local midnight starting `date`.
+pub fn local_midnight(date: NaiveDate) -> i64 {
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    // midnight can be skipped by a DST change, in which case UTC midnight is close enough
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map_or_else(|| midnight.and_utc().timestamp(), |dt| dt.timestamp())
+}
+
+/// Parses a month formatted as `MM[YY]`, defaulting to the current year.
+pub fn parse_month(input: &str) -> Option<NaiveDate> {
+    let re_month = Regex::new(r"^(\d{2})(\d{2})?$").unwrap();
+    let caps = re_month.captures(input)?;
+    let month = caps.get(1).unwrap().as_str().parse::<u32>().ok()?;
+    let year = if let Some(y) = caps.get(2) {
+        2000 + y.as_str().parse::<i32>().ok()?  // assuming 2000+
+    } else {
+        Local::now().year()
+    };
+
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+/// Returns the root of the git work tree containing `dir`, if any.
+/// `.git` is a directory in regular clones and a file in worktrees and submodules.
+pub fn find_git_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Profile names become file names, so they are limited to a safe set of characters.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Shortens paths inside the home directory to start with `~`.
+pub fn home_relative(path: &str) -> String {
+    let Some(home) = dirs::home_dir() else {
+        return path.to_string();
+    };
+
+    match Path::new(path).strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.display()),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// How a project is shown to the user: its name, or its home-relative path.
+pub fn project_label(name: Option<&str>, path: &str) -> String {
+    name.map_or_else(|| home_relative(path), str::to_string)
+}
+
+/// Number of single character insertions, deletions, substitutions and
+/// swaps of adjacent characters needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i chars of a and the first j chars of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// Returns up to three candidates close enough to `input` to be a typo of it,
+/// closest first.
+pub fn suggest<'a>(input: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let input = input.to_lowercase();
+    let max_distance = (input.chars().count() / 3).max(1);
+
+    let mut close: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|c| (edit_distance(&input, &c.to_lowercase()), c.as_str()))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    close.sort();
+
+    close.into_iter().take(3).map(|(_, c)| c).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_duration_combines_units() {
+        assert_eq!(parse_duration("1w2d"), Some(Duration::days(9)));
+        assert_eq!(parse_duration("1H30min"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("0d"), None);
+    }
+
+    #[test]
+    fn parse_duration_rejects_other_text() {
+        assert_eq!(parse_duration("x1w"), None);
+        assert_eq!(parse_duration("1w "), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("backend", "backend"), 0);
+        assert_eq!(edit_distance("backend", "backends"), 1);
+        assert_eq!(edit_distance("backend", "bakend"), 1);
+        assert_eq!(edit_distance("backend", "backand"), 1);
+        assert_eq!(edit_distance("", "web"), 3);
+    }
+
+    #[test]
+    fn edit_distance_counts_transposition_as_one_edit() {
+        assert_eq!(edit_distance("backend", "bakcend"), 1);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+        assert_eq!(edit_distance("abcd", "badc"), 2);
+    }
+
+    #[test]
+    fn suggest_orders_closest_first() {
+        let candidates = names(&["website", "webapp", "web"]);
+        assert_eq!(suggest("wbe", &candidates), vec!["web"]);
+        assert_eq!(suggest("webap", &candidates), vec!["webapp"]);
+    }
+
+    #[test]
+    fn suggest_cuts_off_at_a_third_of_the_input() {
+        let candidates = names(&["api"]);
+        // at least one edit is always allowed
+        assert_eq!(suggest("apo", &candidates), vec!["api"]);
+        assert!(suggest("xyz", &candidates).is_empty());
+
+        let candidates = names(&["frontend"]);
+        assert_eq!(suggest("frnotend", &candidates), vec!["frontend"]);
+        assert_eq!(suggest("fronted", &candidates), vec!["frontend"]);
+        assert!(suggest("frntn", &candidates).is_empty());
+    }
+
+    #[test]
+    fn suggest_ignores_case_and_keeps_candidate_spelling() {
+        let candidates = names(&["Backend"]);
+        assert_eq!(suggest("BAKCEND", &candidates), vec!["Backend"]);
+    }
+
+    #[test]
+    fn suggest_returns_at_most_three() {
+        let candidates = names(&["cat", "bat", "hat", "rat"]);
+        assert_eq!(suggest("mat", &candidates), vec!["bat", "cat", "hat"]);
+    }
+}